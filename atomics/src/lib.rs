@@ -22,6 +22,35 @@ pub use core::sync::atomic::Ordering;
 mod tests {
     use super::*;
 
+    // all atomic types can be used to initialize a `static`
+    static BOOL: AtomicBool = AtomicBool::new(true);
+    static U64: AtomicU64 = AtomicU64::new(1);
+    static I8: AtomicI8 = AtomicI8::new(-1);
+    static F32: AtomicF32 = AtomicF32::new(1.5);
+    static F64: AtomicF64 = AtomicF64::new(-2.5);
+
+    #[test]
+    fn statics() {
+        assert!(BOOL.load(Ordering::Relaxed));
+        assert_eq!(U64.fetch_add(1, Ordering::Relaxed), 1);
+        assert_eq!(I8.load(Ordering::Relaxed), -1);
+        assert_eq!(F32.load(Ordering::Relaxed), 1.5);
+        assert_eq!(F64.fetch_add(1.0, Ordering::Relaxed), -2.5);
+    }
+
+    #[test]
+    fn ordering_helpers() {
+        let x = AtomicU32::new(0);
+        x.store_relaxed(1);
+        assert_eq!(x.load_relaxed(), 1);
+        x.store_release(2);
+        assert_eq!(x.load_acquire(), 2);
+
+        let x = AtomicBool::new(false);
+        x.store_release(true);
+        assert!(x.load_acquire());
+    }
+
     #[test]
     fn usize() {
         let x = AtomicUsize::new(0);
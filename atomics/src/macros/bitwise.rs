@@ -3,7 +3,18 @@
 // http://www.apache.org/licenses/LICENSE-2.0
 
 macro_rules! bitwise {
+    (AtomicBool, bool) => {
+        bitwise!(@impl AtomicBool, bool, |bit: u32| {
+            assert!(bit == 0, "bit index out of range");
+            true
+        });
+    };
     ($name:ident, $type:ty) => {
+        bitwise!(@impl $name, $type, |bit: u32| {
+            (1 as $type).checked_shl(bit).expect("bit index out of range")
+        });
+    };
+    (@impl $name:ident, $type:ty, $mask:expr) => {
         impl Bitwise for $name {
             #[inline]
             fn fetch_and(
@@ -40,6 +51,24 @@ macro_rules! bitwise {
             ) -> <Self as Atomic>::Primitive {
                 self.inner.fetch_xor(value, ordering)
             }
+
+            #[inline]
+            fn fetch_set_bit(&self, bit: u32, ordering: Ordering) -> bool {
+                let mask: $type = ($mask)(bit);
+                self.fetch_or(mask, ordering) & mask == mask
+            }
+
+            #[inline]
+            fn fetch_clear_bit(&self, bit: u32, ordering: Ordering) -> bool {
+                let mask: $type = ($mask)(bit);
+                self.fetch_and(!mask, ordering) & mask == mask
+            }
+
+            #[inline]
+            fn test_bit(&self, bit: u32, ordering: Ordering) -> bool {
+                let mask: $type = ($mask)(bit);
+                self.load(ordering) & mask == mask
+            }
         }
     };
 }
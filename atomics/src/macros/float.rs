@@ -13,8 +13,10 @@ macro_rules! float {
         }
 
         impl $name {
+            /// Creates a new atomic with the provided initial value. This is a
+            /// `const fn` so that it may be used to initialize a `static`.
             #[inline]
-            pub fn new(value: $type) -> $name {
+            pub const fn new(value: $type) -> $name {
                 $name {
                     inner: <$atomic>::new(value.to_bits()),
                 }
@@ -2,69 +2,71 @@
 // Licensed under the Apache License, Version 2.0
 // http://www.apache.org/licenses/LICENSE-2.0
 
+// Floating point types have no native atomic arithmetic, so `fetch_add` and
+// `fetch_sub` are implemented as compare-and-swap loops over the bit pattern
+// of the value. Each iteration computes the result from the most recently
+// observed value and retries until no other thread has modified the value in
+// the meantime, so concurrent updates are never lost.
+//
+// As the comparison is performed on the bit pattern rather than by floating
+// point equality, the loop terminates even when the stored value is NaN. The
+// arithmetic itself follows IEEE 754, so once the stored value is NaN (or a
+// NaN is added) it remains NaN, and overflow results in an infinity.
 macro_rules! float_arithmetic {
     ($name:ident, $type:ty) => {
-        impl Arithmetic for $name {
+        impl $name {
             #[inline]
-            fn fetch_add(
-                &self,
-                value: <Self as Atomic>::Primitive,
-                ordering: Ordering,
-            ) -> <Self as Atomic>::Primitive {
+            fn fetch_update_bits(&self, ordering: Ordering, f: impl Fn($type) -> $type) -> $type {
                 let load_ordering = match ordering {
                     Ordering::AcqRel => Ordering::Acquire,
                     Ordering::Release => Ordering::Relaxed,
                     _ => ordering,
                 };
-                let current = self.inner.load(load_ordering);
-                let mut new = <$type>::from_bits(current) + value;
+                let mut current = self.inner.load(load_ordering);
                 loop {
-                    let result = self.inner.compare_exchange(
-                        current,
-                        new.to_bits(),
-                        ordering,
-                        load_ordering,
-                    );
-                    match result {
-                        Ok(v) => {
-                            return <$type>::from_bits(v);
+                    let new = f(<$type>::from_bits(current)).to_bits();
+                    match self
+                        .inner
+                        .compare_exchange_weak(current, new, ordering, load_ordering)
+                    {
+                        Ok(previous) => {
+                            return <$type>::from_bits(previous);
                         }
-                        Err(v) => {
-                            new = <$type>::from_bits(v) + value;
+                        Err(previous) => {
+                            current = previous;
                         }
                     }
                 }
             }
+        }
+
+        impl Arithmetic for $name {
+            /// Adds to the current value, returning the previous value.
+            ///
+            /// This is implemented as a compare-and-swap loop and follows IEEE
+            /// 754 semantics: overflow results in an infinity rather than
+            /// wrapping, and if either value is NaN the result is NaN.
+            #[inline]
+            fn fetch_add(
+                &self,
+                value: <Self as Atomic>::Primitive,
+                ordering: Ordering,
+            ) -> <Self as Atomic>::Primitive {
+                self.fetch_update_bits(ordering, |current| current + value)
+            }
 
+            /// Subtracts from the current value, returning the previous value.
+            ///
+            /// This is implemented as a compare-and-swap loop and follows IEEE
+            /// 754 semantics: overflow results in an infinity rather than
+            /// wrapping, and if either value is NaN the result is NaN.
             #[inline]
             fn fetch_sub(
                 &self,
                 value: <Self as Atomic>::Primitive,
                 ordering: Ordering,
             ) -> <Self as Atomic>::Primitive {
-                let load_ordering = match ordering {
-                    Ordering::AcqRel => Ordering::Acquire,
-                    Ordering::Release => Ordering::Relaxed,
-                    _ => ordering,
-                };
-                let current = self.inner.load(load_ordering);
-                let mut new = <$type>::from_bits(current) - value;
-                loop {
-                    let result = self.inner.compare_exchange(
-                        current,
-                        new.to_bits(),
-                        ordering,
-                        load_ordering,
-                    );
-                    match result {
-                        Ok(v) => {
-                            return <$type>::from_bits(v);
-                        }
-                        Err(v) => {
-                            new = <$type>::from_bits(v) - value;
-                        }
-                    }
-                }
+                self.fetch_update_bits(ordering, |current| current - value)
             }
         }
     };
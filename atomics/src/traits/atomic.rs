@@ -25,6 +25,34 @@ pub trait Atomic {
     /// and `Relaxed`.
     fn store(&self, value: Self::Primitive, order: Ordering);
 
+    /// Loads the value from the atomic type with `Ordering::Relaxed`. This is
+    /// suitable for values which are read independently of other memory, for
+    /// example, counters.
+    #[inline]
+    fn load_relaxed(&self) -> Self::Primitive {
+        self.load(Ordering::Relaxed)
+    }
+
+    /// Loads the value from the atomic type with `Ordering::Acquire`. This
+    /// pairs with `store_release` to observe writes made before the store.
+    #[inline]
+    fn load_acquire(&self) -> Self::Primitive {
+        self.load(Ordering::Acquire)
+    }
+
+    /// Stores a value into the atomic type with `Ordering::Relaxed`.
+    #[inline]
+    fn store_relaxed(&self, value: Self::Primitive) {
+        self.store(value, Ordering::Relaxed)
+    }
+
+    /// Stores a value into the atomic type with `Ordering::Release`. This
+    /// pairs with `load_acquire` to publish writes made before the store.
+    #[inline]
+    fn store_release(&self, value: Self::Primitive) {
+        self.store(value, Ordering::Release)
+    }
+
     /// Stores a value into the atomic type, returning the previous value.
     /// `swap` takes an `Ordering` argument which describes the memory ordering
     /// of this operation. All ordering modes are possible. Note that using
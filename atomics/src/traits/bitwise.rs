@@ -53,4 +53,31 @@ pub trait Bitwise: Atomic {
         value: <Self as Atomic>::Primitive,
         ordering: Ordering,
     ) -> <Self as Atomic>::Primitive;
+
+    /// Sets the bit at the provided index, where `0` is the least significant
+    /// bit, returning whether the bit was previously set. This is built on
+    /// `fetch_or` and takes the same `Ordering` argument.
+    ///
+    /// # Panics
+    /// Panics if the index is not less than the number of bits in the type.
+    /// For `AtomicBool`, the only valid index is `0`.
+    fn fetch_set_bit(&self, bit: u32, ordering: Ordering) -> bool;
+
+    /// Clears the bit at the provided index, where `0` is the least
+    /// significant bit, returning whether the bit was previously set. This is
+    /// built on `fetch_and` and takes the same `Ordering` argument.
+    ///
+    /// # Panics
+    /// Panics if the index is not less than the number of bits in the type.
+    /// For `AtomicBool`, the only valid index is `0`.
+    fn fetch_clear_bit(&self, bit: u32, ordering: Ordering) -> bool;
+
+    /// Returns whether the bit at the provided index is set, where `0` is the
+    /// least significant bit. This loads the value with the provided
+    /// `Ordering`, which must not be `Release` or `AcqRel`.
+    ///
+    /// # Panics
+    /// Panics if the index is not less than the number of bits in the type.
+    /// For `AtomicBool`, the only valid index is `0`.
+    fn test_bit(&self, bit: u32, ordering: Ordering) -> bool;
 }
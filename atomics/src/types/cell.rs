@@ -0,0 +1,197 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use crate::*;
+
+use core::marker::PhantomData;
+use core::mem::size_of;
+
+/// Types which can be stored in an `AtomicCell`.
+///
+/// # Safety
+/// Implementors must be at most 8 bytes in size and must not contain any
+/// padding bytes, as the value is stored by copying its bytes into a 64 bit
+/// integer and compared by comparing those bytes.
+pub unsafe trait Packable: Copy {}
+
+macro_rules! packable {
+    ($($type:ty),*) => {
+        $(
+            unsafe impl Packable for $type {}
+        )*
+    };
+}
+
+packable!(bool, char, u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+// homogeneous tuples and arrays have no padding between their elements
+unsafe impl<T: Packable> Packable for (T, T) {}
+unsafe impl<T: Packable> Packable for (T, T, T, T) {}
+unsafe impl<T: Packable, const N: usize> Packable for [T; N] {}
+
+/// A small `Copy` value which can be shared between threads, for example, a
+/// pair of `u32`s which must be updated together.
+///
+/// The value is stored in an `AtomicU64`, so all operations are lock-free.
+/// Constructing an `AtomicCell` for a type which is larger than 8 bytes fails
+/// to compile.
+///
+/// Note that `compare_exchange` compares the bytes of the values, so, for
+/// example, floating point values are compared by their bit patterns.
+///
+/// # Example
+/// ```
+/// # use rustcommon_atomics::*;
+/// static RANGE: AtomicCell<(u32, u32)> = AtomicCell::new((0, 0));
+///
+/// RANGE.store((1, 10), Ordering::Relaxed);
+/// assert_eq!(RANGE.load(Ordering::Relaxed), (1, 10));
+/// ```
+pub struct AtomicCell<T: Packable> {
+    inner: core::sync::atomic::AtomicU64,
+    _value: PhantomData<T>,
+}
+
+impl<T: Packable> AtomicCell<T> {
+    const SIZE_CHECK: () = assert!(
+        size_of::<T>() <= size_of::<u64>(),
+        "type is too large for an AtomicCell"
+    );
+
+    /// Creates a new `AtomicCell` holding the provided value.
+    pub const fn new(value: T) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::SIZE_CHECK;
+        Self {
+            inner: core::sync::atomic::AtomicU64::new(Self::to_bits(value)),
+            _value: PhantomData,
+        }
+    }
+
+    /// Loads the value from the cell.
+    ///
+    /// `load` takes an `Ordering` argument which describes the memory ordering
+    /// of this operation. Possible values are `SeqCst`, `Acquire` and
+    /// `Relaxed`.
+    pub fn load(&self, ordering: Ordering) -> T {
+        Self::from_bits(self.inner.load(ordering))
+    }
+
+    /// Stores a value into the cell.
+    ///
+    /// `store` takes an `Ordering` argument which describes the memory
+    /// ordering of this operation. Possible values are `SeqCst`, `Release`
+    /// and `Relaxed`.
+    pub fn store(&self, value: T, ordering: Ordering) {
+        self.inner.store(Self::to_bits(value), ordering)
+    }
+
+    /// Stores a value into the cell, returning the previous value.
+    pub fn swap(&self, value: T, ordering: Ordering) -> T {
+        Self::from_bits(self.inner.swap(Self::to_bits(value), ordering))
+    }
+
+    /// Stores a value into the cell if the current value is the same as the
+    /// `current` value. The return value is a result indicating whether the
+    /// new value was written and containing the previous value.
+    pub fn compare_exchange(
+        &self,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T> {
+        self.inner
+            .compare_exchange(Self::to_bits(current), Self::to_bits(new), success, failure)
+            .map(Self::from_bits)
+            .map_err(Self::from_bits)
+    }
+
+    /// Stores a value into the cell if the current value is the same as the
+    /// `current` value. Unlike `compare_exchange`, this function is allowed to
+    /// spuriously fail even when the comparison succeeds, which can result in
+    /// more efficient code on some platforms.
+    pub fn compare_exchange_weak(
+        &self,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T> {
+        self.inner
+            .compare_exchange_weak(Self::to_bits(current), Self::to_bits(new), success, failure)
+            .map(Self::from_bits)
+            .map_err(Self::from_bits)
+    }
+
+    /// Consumes the cell and returns the contained value.
+    pub fn into_inner(self) -> T {
+        Self::from_bits(self.inner.into_inner())
+    }
+
+    const fn to_bits(value: T) -> u64 {
+        let mut bits = 0_u64;
+        // SAFETY: `T` is no larger than a `u64` and has no padding, so all of
+        // the bytes copied are initialized
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                &value as *const T as *const u8,
+                &mut bits as *mut u64 as *mut u8,
+                size_of::<T>(),
+            );
+        }
+        bits
+    }
+
+    fn from_bits(bits: u64) -> T {
+        // SAFETY: the bits were produced by `to_bits` from a valid `T`
+        unsafe { core::ptr::read_unaligned(&bits as *const u64 as *const T) }
+    }
+}
+
+impl<T: Packable + Default> Default for AtomicCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Packable + core::fmt::Debug> core::fmt::Debug for AtomicCell<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("AtomicCell")
+            .field(&self.load(Ordering::SeqCst))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pair() {
+        let cell = AtomicCell::new((1_u32, 2_u32));
+        assert_eq!(cell.load(Ordering::SeqCst), (1, 2));
+        assert_eq!(cell.swap((3, 4), Ordering::SeqCst), (1, 2));
+        assert_eq!(
+            cell.compare_exchange((3, 4), (5, 6), Ordering::SeqCst, Ordering::SeqCst),
+            Ok((3, 4))
+        );
+        assert_eq!(
+            cell.compare_exchange((3, 4), (7, 8), Ordering::SeqCst, Ordering::SeqCst),
+            Err((5, 6))
+        );
+        assert_eq!(cell.into_inner(), (5, 6));
+    }
+
+    #[test]
+    fn small() {
+        let cell = AtomicCell::new([1_u8, 2, 3]);
+        cell.store([4, 5, 6], Ordering::SeqCst);
+        assert_eq!(cell.load(Ordering::SeqCst), [4, 5, 6]);
+
+        let cell = AtomicCell::<f32>::default();
+        assert_eq!(cell.swap(-1.5, Ordering::SeqCst), 0.0);
+        assert_eq!(cell.load(Ordering::SeqCst), -1.5);
+    }
+}
@@ -184,7 +184,7 @@ mod tests {
         let atomic = AtomicF32::new(0.0);
         loop {
             if atomic
-                .compare_exchange(
+                .compare_exchange_weak(
                     0.0,
                     std::f32::consts::PI,
                     Ordering::SeqCst,
@@ -197,4 +197,57 @@ mod tests {
         }
         assert_eq!(atomic.load(Ordering::SeqCst), std::f32::consts::PI);
     }
+
+    #[test]
+    fn fetch_add() {
+        let atomic = AtomicF32::new(1.5);
+        assert_eq!(atomic.fetch_add(2.25, Ordering::SeqCst), 1.5);
+        assert_eq!(atomic.load(Ordering::SeqCst), 3.75);
+    }
+
+    #[test]
+    fn fetch_sub() {
+        let atomic = AtomicF32::new(1.5);
+        assert_eq!(atomic.fetch_sub(2.25, Ordering::SeqCst), 1.5);
+        assert_eq!(atomic.load(Ordering::SeqCst), -0.75);
+    }
+
+    #[test]
+    fn fetch_add_nan() {
+        let atomic = AtomicF32::new(f32::NAN);
+        assert!(atomic.fetch_add(1.0, Ordering::SeqCst).is_nan());
+        assert!(atomic.load(Ordering::SeqCst).is_nan());
+
+        let atomic = AtomicF32::new(1.0);
+        assert_eq!(atomic.fetch_sub(f32::NAN, Ordering::SeqCst), 1.0);
+        assert!(atomic.load(Ordering::SeqCst).is_nan());
+    }
+
+    #[test]
+    fn fetch_add_overflow() {
+        let atomic = AtomicF32::new(f32::MAX);
+        atomic.fetch_add(f32::MAX, Ordering::SeqCst);
+        assert_eq!(atomic.load(Ordering::SeqCst), f32::INFINITY);
+    }
+
+    #[test]
+    fn concurrent_fetch_add() {
+        let atomic = std::sync::Arc::new(AtomicF32::new(0.0));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let atomic = atomic.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..10_000 {
+                        atomic.fetch_add(1.0, Ordering::Relaxed);
+                        atomic.fetch_add(1.0, Ordering::Relaxed);
+                        atomic.fetch_sub(1.0, Ordering::Relaxed);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(atomic.load(Ordering::SeqCst), 40_000.0);
+    }
 }
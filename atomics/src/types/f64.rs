@@ -172,7 +172,7 @@ mod tests {
         let atomic = AtomicF64::new(0.0);
         loop {
             if atomic
-                .compare_exchange(
+                .compare_exchange_weak(
                     0.0,
                     std::f64::consts::PI,
                     Ordering::SeqCst,
@@ -185,4 +185,57 @@ mod tests {
         }
         assert_eq!(atomic.load(Ordering::SeqCst), std::f64::consts::PI);
     }
+
+    #[test]
+    fn fetch_add() {
+        let atomic = AtomicF64::new(1.5);
+        assert_eq!(atomic.fetch_add(2.25, Ordering::SeqCst), 1.5);
+        assert_eq!(atomic.load(Ordering::SeqCst), 3.75);
+    }
+
+    #[test]
+    fn fetch_sub() {
+        let atomic = AtomicF64::new(1.5);
+        assert_eq!(atomic.fetch_sub(2.25, Ordering::SeqCst), 1.5);
+        assert_eq!(atomic.load(Ordering::SeqCst), -0.75);
+    }
+
+    #[test]
+    fn fetch_add_nan() {
+        let atomic = AtomicF64::new(f64::NAN);
+        assert!(atomic.fetch_add(1.0, Ordering::SeqCst).is_nan());
+        assert!(atomic.load(Ordering::SeqCst).is_nan());
+
+        let atomic = AtomicF64::new(1.0);
+        assert_eq!(atomic.fetch_sub(f64::NAN, Ordering::SeqCst), 1.0);
+        assert!(atomic.load(Ordering::SeqCst).is_nan());
+    }
+
+    #[test]
+    fn fetch_add_overflow() {
+        let atomic = AtomicF64::new(f64::MAX);
+        atomic.fetch_add(f64::MAX, Ordering::SeqCst);
+        assert_eq!(atomic.load(Ordering::SeqCst), f64::INFINITY);
+    }
+
+    #[test]
+    fn concurrent_fetch_add() {
+        let atomic = std::sync::Arc::new(AtomicF64::new(0.0));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let atomic = atomic.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..10_000 {
+                        atomic.fetch_add(1.0, Ordering::Relaxed);
+                        atomic.fetch_add(1.0, Ordering::Relaxed);
+                        atomic.fetch_sub(1.0, Ordering::Relaxed);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(atomic.load(Ordering::SeqCst), 40_000.0);
+    }
 }
@@ -178,7 +178,7 @@ mod tests {
         let atomic = AtomicI8::new(0);
         loop {
             if atomic
-                .compare_exchange(0, 1, Ordering::SeqCst, Ordering::SeqCst)
+                .compare_exchange_weak(0, 1, Ordering::SeqCst, Ordering::SeqCst)
                 .is_ok()
             {
                 break;
@@ -186,4 +186,25 @@ mod tests {
         }
         assert_eq!(atomic.load(Ordering::SeqCst), 1);
     }
+
+    #[test]
+    fn bits() {
+        let atomic = AtomicI8::new(0);
+        assert!(!atomic.test_bit(0, Ordering::SeqCst));
+        assert!(!atomic.fetch_set_bit(0, Ordering::SeqCst));
+        assert!(atomic.fetch_set_bit(0, Ordering::SeqCst));
+        assert!(!atomic.fetch_set_bit(7, Ordering::SeqCst));
+        assert!(atomic.test_bit(7, Ordering::SeqCst));
+        assert!(!atomic.test_bit(1, Ordering::SeqCst));
+        assert!(atomic.fetch_clear_bit(0, Ordering::SeqCst));
+        assert!(!atomic.fetch_clear_bit(0, Ordering::SeqCst));
+        assert!(atomic.test_bit(7, Ordering::SeqCst));
+    }
+
+    #[test]
+    #[should_panic]
+    fn bit_out_of_range() {
+        let atomic = AtomicI8::new(0);
+        atomic.fetch_set_bit(8, Ordering::SeqCst);
+    }
 }
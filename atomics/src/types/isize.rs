@@ -170,7 +170,7 @@ mod tests {
         let atomic = AtomicIsize::new(0);
         loop {
             if atomic
-                .compare_exchange(0, 1, Ordering::SeqCst, Ordering::SeqCst)
+                .compare_exchange_weak(0, 1, Ordering::SeqCst, Ordering::SeqCst)
                 .is_ok()
             {
                 break;
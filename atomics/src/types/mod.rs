@@ -40,3 +40,9 @@ pub use self::u64::*;
 
 mod usize;
 pub use self::usize::*;
+
+mod cell;
+pub use self::cell::*;
+
+mod saturating;
+pub use self::saturating::*;
@@ -0,0 +1,115 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use crate::*;
+
+use core::ops::Deref;
+
+/// A wrapper around an atomic type which saturates at the numeric bounds
+/// instead of wrapping, for example, for counters which must never appear to
+/// go backwards.
+///
+/// The `add` and `sub` operations use `Ordering::Relaxed`, which is sufficient
+/// for counters and statistics. The wrapped atomic is available through
+/// `Deref` for operations which need a different ordering.
+///
+/// # Example
+/// ```
+/// # use rustcommon_atomics::*;
+/// static COUNT: Saturating<AtomicU8> = Saturating::new(AtomicU8::new(250));
+///
+/// assert_eq!(COUNT.add(10), 250);
+/// assert_eq!(COUNT.get(), u8::MAX);
+/// ```
+#[derive(Default, Debug)]
+pub struct Saturating<T: SaturatingArithmetic> {
+    inner: T,
+}
+
+impl<T: SaturatingArithmetic> Saturating<T> {
+    /// Wraps the provided atomic. This is a `const fn` so that it may be used
+    /// to initialize a `static`.
+    pub const fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    /// Adds to the current value, saturating at the numeric bound, and returns
+    /// the previous value.
+    #[inline]
+    pub fn add(&self, value: <T as Atomic>::Primitive) -> <T as Atomic>::Primitive {
+        self.inner.fetch_saturating_add(value, Ordering::Relaxed)
+    }
+
+    /// Subtracts from the current value, saturating at the numeric bound, and
+    /// returns the previous value.
+    #[inline]
+    pub fn sub(&self, value: <T as Atomic>::Primitive) -> <T as Atomic>::Primitive {
+        self.inner.fetch_saturating_sub(value, Ordering::Relaxed)
+    }
+
+    /// Returns the current value.
+    #[inline]
+    pub fn get(&self) -> <T as Atomic>::Primitive {
+        self.inner.load(Ordering::Relaxed)
+    }
+
+    /// Replaces the current value.
+    #[inline]
+    pub fn set(&self, value: <T as Atomic>::Primitive) {
+        self.inner.store(value, Ordering::Relaxed)
+    }
+
+    /// Consumes the wrapper, returning the wrapped atomic.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: SaturatingArithmetic> Deref for Saturating<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: SaturatingArithmetic> From<T> for Saturating<T> {
+    fn from(inner: T) -> Self {
+        Self::new(inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsigned() {
+        let value = Saturating::new(AtomicU64::new(1));
+        assert_eq!(value.sub(2), 1);
+        assert_eq!(value.get(), 0);
+        value.set(u64::MAX - 1);
+        assert_eq!(value.add(2), u64::MAX - 1);
+        assert_eq!(value.get(), u64::MAX);
+    }
+
+    #[test]
+    fn signed() {
+        let value = Saturating::from(AtomicI8::new(-127));
+        assert_eq!(value.sub(10), -127);
+        assert_eq!(value.get(), i8::MIN);
+        assert_eq!(value.add(127), i8::MIN);
+        assert_eq!(value.add(127), -1);
+        assert_eq!(value.add(127), 126);
+        assert_eq!(value.get(), i8::MAX);
+    }
+
+    #[test]
+    fn deref() {
+        let value = Saturating::new(AtomicU32::new(u32::MAX));
+        assert_eq!(value.fetch_saturating_add(1, Ordering::SeqCst), u32::MAX);
+        assert_eq!(value.load(Ordering::SeqCst), u32::MAX);
+        assert_eq!(value.into_inner().load(Ordering::SeqCst), u32::MAX);
+    }
+}
@@ -166,7 +166,7 @@ mod tests {
         let atomic = AtomicU64::new(0);
         loop {
             if atomic
-                .compare_exchange(0, 1, Ordering::SeqCst, Ordering::SeqCst)
+                .compare_exchange_weak(0, 1, Ordering::SeqCst, Ordering::SeqCst)
                 .is_ok()
             {
                 break;
@@ -174,4 +174,25 @@ mod tests {
         }
         assert_eq!(atomic.load(Ordering::SeqCst), 1);
     }
+
+    #[test]
+    fn bits() {
+        let atomic = AtomicU64::new(0);
+        assert!(!atomic.test_bit(0, Ordering::SeqCst));
+        assert!(!atomic.fetch_set_bit(0, Ordering::SeqCst));
+        assert!(atomic.fetch_set_bit(0, Ordering::SeqCst));
+        assert!(!atomic.fetch_set_bit(63, Ordering::SeqCst));
+        assert!(atomic.test_bit(63, Ordering::SeqCst));
+        assert!(!atomic.test_bit(1, Ordering::SeqCst));
+        assert!(atomic.fetch_clear_bit(0, Ordering::SeqCst));
+        assert!(!atomic.fetch_clear_bit(0, Ordering::SeqCst));
+        assert!(atomic.test_bit(63, Ordering::SeqCst));
+    }
+
+    #[test]
+    #[should_panic]
+    fn bit_out_of_range() {
+        let atomic = AtomicU64::new(0);
+        atomic.fetch_set_bit(64, Ordering::SeqCst);
+    }
 }
@@ -14,12 +14,17 @@ pub enum Error {
     /// The provided percentile is outside of the range 0.0 - 100.0 (inclusive)
     InvalidPercentile,
     #[error("value out of range")]
-    /// The provided value is outside of the storable range.
+    /// The provided value is outside of the storable range, or the provided
+    /// instant is older than the span of time covered by the heatmap.
     OutOfRange,
     #[error("invalid heatmap config")]
     /// The heatmap configuration is invalid, see docs for `Heatmap::new()` for
     /// the constraints.
     InvalidConfig,
+    #[error("incompatible heatmap")]
+    /// The heatmaps have different bucket configurations and cannot be
+    /// combined.
+    IncompatibleHeatmap,
 }
 
 impl From<HistogramError> for Error {
@@ -29,12 +34,7 @@ impl From<HistogramError> for Error {
             HistogramError::InvalidPercentile => Self::InvalidPercentile,
             HistogramError::OutOfRange => Self::OutOfRange,
             HistogramError::InvalidConfig => Self::InvalidConfig,
-            HistogramError::IncompatibleHistogram => {
-                // SAFETY: a heatmap has histograms which all have the same
-                // configuration and therefore the operations which act on two
-                // histograms will always have two compatible histograms
-                panic!("imposible state")
-            }
+            HistogramError::IncompatibleHistogram => Self::IncompatibleHeatmap,
         }
     }
 }
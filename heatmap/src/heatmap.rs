@@ -4,9 +4,17 @@
 
 use crate::Error;
 use crate::*;
+use core::marker::PhantomData;
 use core::sync::atomic::*;
+use rustcommon_time::{Duration, Instant};
 
 use histogram::{Bucket, Histogram};
+use parking_lot::RwLock;
+#[cfg(feature = "streamstats")]
+use rustcommon_atomics::AtomicU64;
+#[cfg(feature = "streamstats")]
+use rustcommon_streamstats::AtomicStreamstats;
+use std::sync::Arc;
 
 /// A `Heatmap` stores counts for timestamped values over a configured span of
 /// time.
@@ -18,17 +26,48 @@ use histogram::{Bucket, Histogram};
 ///
 /// This acts as a moving histogram, such that requesting a percentile returns
 /// a percentile from across the configured span of time.
-pub struct Heatmap {
-    slices: Vec<Histogram>,
+///
+/// The clock used for the time domain is selected by the `TimeUnit` parameter,
+/// which defaults to `Nanoseconds<u64>`. Heatmaps covering a long span with a
+/// coarse resolution can use `Seconds<u32>` instead.
+///
+/// With the `streamstats` feature enabled, a heatmap built with
+/// `Builder::samples()` additionally retains a bounded buffer of raw samples
+/// for each window, which is used to report percentiles as exact values rather
+/// than histogram buckets. This trades memory for accuracy and is most useful
+/// for small windows, for example, when debugging tail latency.
+///
+/// By default, the histograms for every window are allocated up front. A
+/// heatmap built with `Builder::lazy(true)` instead allocates the histogram for
+/// a window when it first receives a count and frees it when the window ages
+/// out, so an idle heatmap uses little memory.
+///
+/// All of the counts are stored in atomics, so a `Heatmap` can be recorded
+/// into concurrently without any additional synchronization. There is no
+/// separate atomic variant of the type: to render or serialize a heatmap
+/// without racing with writers, `clone()` it to take a snapshot and operate on
+/// the copy.
+pub struct Heatmap<U: TimeUnit = Nanoseconds<u64>> {
+    slices: Vec<Slice>,
+    // an empty histogram which is used to allocate lazy windows and which is
+    // reported for windows that are not allocated
+    empty: Option<Arc<Histogram>>,
     current: AtomicUsize,
-    next_tick: AtomicInstant,
-    resolution: Duration,
+    next_tick: Instant<U::Atomic>,
+    resolution: Duration<U>,
     summary: Histogram,
+    // the raw samples for each window, empty unless enabled with
+    // `Builder::samples()`
+    #[cfg(feature = "streamstats")]
+    samples: Vec<Samples>,
+    // the number of raw samples retained for each window
+    #[cfg(feature = "streamstats")]
+    sample_capacity: usize,
 }
 
 /// A `Builder` allows for constructing a `Heatmap` with the desired
 /// configuration.
-pub struct Builder {
+pub struct Builder<U: TimeUnit = Nanoseconds<u64>> {
     // minimum resolution parameter `M = 2^m`
     m: u32,
     // minimum resolution range parameter `R = 2^r - 1`
@@ -36,15 +75,188 @@ pub struct Builder {
     // maximum value parameter `N = 2^n - 1`
     n: u32,
     // span of time represented by the heatmap
-    span: Duration,
+    span: Duration<U>,
     // the resolution in the time domain
-    resolution: Duration,
+    resolution: Duration<U>,
+    // the number of raw samples retained for each window, zero if disabled
+    #[cfg(feature = "streamstats")]
+    samples: usize,
+    // allocate the histogram for each window on first use
+    lazy: bool,
+}
+
+/// The storage for the histogram of a single window.
+enum Slice {
+    /// The histogram is allocated up front and reused as the window ages out.
+    Allocated(Arc<Histogram>),
+    /// The histogram is allocated when the window first receives a count and
+    /// is freed when the window ages out.
+    Lazy(RwLock<Option<Arc<Histogram>>>),
+}
+
+impl Slice {
+    /// Returns the histogram for this window, if it is allocated.
+    fn histogram(&self) -> Option<Arc<Histogram>> {
+        match self {
+            Self::Allocated(histogram) => Some(histogram.clone()),
+            Self::Lazy(histogram) => histogram.read().clone(),
+        }
+    }
+}
+
+/// The raw samples retained for a single window, along with the total count
+/// recorded into the window. Once a window receives more counts than its buffer
+/// can hold, the retained samples stand in for all of the counts.
+#[cfg(feature = "streamstats")]
+struct Samples {
+    count: core::sync::atomic::AtomicU64,
+    retained: Retained,
+}
+
+/// The buffer of raw samples for a single window. As with `Slice`, the buffer
+/// for a lazy heatmap is allocated when the window first receives a count and
+/// is freed when the window ages out.
+#[cfg(feature = "streamstats")]
+enum Retained {
+    Allocated(AtomicStreamstats<AtomicU64>),
+    Lazy(RwLock<Option<AtomicStreamstats<AtomicU64>>>),
+}
+
+#[cfg(feature = "streamstats")]
+impl Samples {
+    fn new(capacity: usize, lazy: bool) -> Self {
+        let retained = if lazy {
+            Retained::Lazy(RwLock::new(None))
+        } else {
+            Retained::Allocated(AtomicStreamstats::new(capacity))
+        };
+        Self {
+            count: core::sync::atomic::AtomicU64::new(0),
+            retained,
+        }
+    }
+
+    fn insert(&self, value: u64, count: u32, capacity: usize) {
+        self.count.fetch_add(count as u64, Ordering::Relaxed);
+        // there's no need to insert more copies of the value than the buffer
+        // can hold
+        let insert = |samples: &AtomicStreamstats<AtomicU64>| {
+            for _ in 0..(count as usize).min(samples.capacity()) {
+                samples.insert(value);
+            }
+        };
+        match &self.retained {
+            Retained::Allocated(samples) => insert(samples),
+            Retained::Lazy(samples) => {
+                if let Some(samples) = samples.read().as_ref() {
+                    insert(samples);
+                    return;
+                }
+                insert(
+                    samples
+                        .write()
+                        .get_or_insert_with(|| AtomicStreamstats::new(capacity)),
+                );
+            }
+        }
+    }
+
+    /// Returns the retained samples, each paired with the number of counts in
+    /// the window that it represents.
+    fn weighted(&self) -> Vec<(u64, f64)> {
+        let samples = match &self.retained {
+            Retained::Allocated(samples) => samples.samples(),
+            Retained::Lazy(samples) => samples
+                .read()
+                .as_ref()
+                .map(|samples| samples.samples())
+                .unwrap_or_default(),
+        };
+        if samples.is_empty() {
+            return Vec::new();
+        }
+        let weight = self.count.load(Ordering::Relaxed) as f64 / samples.len() as f64;
+        samples.into_iter().map(|v| (v, weight)).collect()
+    }
+
+    fn clear(&self) {
+        match &self.retained {
+            Retained::Allocated(samples) => samples.clear(),
+            Retained::Lazy(samples) => {
+                samples.write().take();
+            }
+        }
+        self.count.store(0, Ordering::Relaxed);
+    }
 }
 
-impl Builder {
+#[cfg(feature = "streamstats")]
+impl Clone for Samples {
+    fn clone(&self) -> Self {
+        let copy = |samples: &AtomicStreamstats<AtomicU64>| {
+            let clone = AtomicStreamstats::new(samples.capacity());
+            for value in samples.chronological_samples() {
+                clone.insert(value);
+            }
+            clone
+        };
+        let retained = match &self.retained {
+            Retained::Allocated(samples) => Retained::Allocated(copy(samples)),
+            Retained::Lazy(samples) => {
+                Retained::Lazy(RwLock::new(samples.read().as_ref().map(copy)))
+            }
+        };
+        Self {
+            count: core::sync::atomic::AtomicU64::new(self.count.load(Ordering::Relaxed)),
+            retained,
+        }
+    }
+}
+
+impl<U: TimeUnit> Builder<U> {
+    /// Creates a `Builder` with the default values `m = 0`, `r = 10`, `n = 30`,
+    /// `span = 60s`, `resolution = 1s`. See `Heatmap::builder()` for details.
+    ///
+    /// Unlike `Heatmap::builder()`, this allows the `TimeUnit` to be selected.
+    /// For example, a heatmap covering the past week with a resolution of one
+    /// minute:
+    ///
+    /// ```
+    /// use heatmap::Builder;
+    /// use rustcommon_time::{Duration, Seconds};
+    ///
+    /// let heatmap = Builder::<Seconds<u32>>::new()
+    ///     .span(Duration::<Seconds<u32>>::from_secs(7 * 86_400))
+    ///     .resolution(Duration::<Seconds<u32>>::from_secs(60))
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(heatmap.windows(), 10_080);
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            m: 0,
+            r: 10,
+            n: 30,
+            span: U::from_secs(60),
+            resolution: U::from_secs(1),
+            #[cfg(feature = "streamstats")]
+            samples: 0,
+            lazy: false,
+        }
+    }
+
     /// Consume the `Builder` and return a `Heatmap`.
-    pub fn build(self) -> Result<Heatmap, Error> {
-        Heatmap::new(self.m, self.r, self.n, self.span, self.resolution)
+    pub fn build(self) -> Result<Heatmap<U>, Error> {
+        Heatmap::with_config(
+            self.m,
+            self.r,
+            self.n,
+            self.span,
+            self.resolution,
+            self.lazy,
+            #[cfg(feature = "streamstats")]
+            self.samples,
+        )
     }
 
     /// Sets the width of the smallest bucket in the `Heatmap`.
@@ -80,7 +292,7 @@ impl Builder {
     /// Sets the duration that is covered by the `Heatmap`.
     ///
     /// Values that are older than the duration will be dropped as they age-out.
-    pub fn span(mut self, duration: Duration) -> Self {
+    pub fn span(mut self, duration: Duration<U>) -> Self {
         self.span = duration;
         self
     }
@@ -89,13 +301,66 @@ impl Builder {
     ///
     /// Increments with similar timestamps will be grouped together and age-out
     /// together.
-    pub fn resolution(mut self, duration: Duration) -> Self {
+    pub fn resolution(mut self, duration: Duration<U>) -> Self {
         self.resolution = duration;
         self
     }
+
+    /// Sets whether the histogram for each window is allocated lazily.
+    ///
+    /// When enabled, a window's histogram is only allocated once the window
+    /// receives a count and is freed when the window ages out. This reduces the
+    /// memory used by heatmaps which cover a long span with a fine resolution
+    /// but receive few counts, at the cost of an allocation and a lock on
+    /// increments. Windows without a histogram are reported as empty.
+    pub fn lazy(mut self, lazy: bool) -> Self {
+        self.lazy = lazy;
+        self
+    }
+
+    /// Sets the number of raw samples retained for each window. Defaults to
+    /// zero, which disables the sample buffers.
+    ///
+    /// When enabled, `Heatmap::percentile()` reports values from the retained
+    /// samples rather than from the histogram buckets. These are exact as long
+    /// as no window has received more counts than it can retain. Past that
+    /// point, the oldest samples in the window are overwritten and the
+    /// remaining samples are weighted to stand in for every count recorded
+    /// into the window, so the percentile becomes an estimate.
+    ///
+    /// Each sample requires 8 bytes, so the total memory used for samples is
+    /// `8 * samples * windows` bytes. For a lazy heatmap, the samples are only
+    /// allocated for windows with counts. Every call to `percentile()` copies
+    /// and sorts all of the retained samples.
+    #[cfg(feature = "streamstats")]
+    pub fn samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+}
+
+impl<U: TimeUnit> Default for Builder<U> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Heatmap {
+    /// Creates a `Builder` with the default values `m = 0`, `r = 10`, `n = 30`,
+    /// `span = 60s`, `resolution = 1s`.
+    ///
+    /// This would create a `Heatmap` with 61 total `Histogram`s, each with
+    /// 11264 buckets which can store values from 1 to 1_073_741_823 with
+    /// values 1 to 1023 being stored in buckets with a width of 1. Such a
+    /// `Heatmap` would be appropriate for latencies measured in nanoseconds
+    /// where the max expected latency is one second and reporting covers the
+    /// past minute.
+    pub fn builder() -> Builder {
+        Builder::new()
+    }
+}
+
+impl<U: TimeUnit> Heatmap<U> {
     /// Create a new `Heatmap` which stores counts for timestamped values over
     /// a configured span of time.
     ///
@@ -115,24 +380,73 @@ impl Heatmap {
     ///
     /// - `resolution` - sets the resolution in the time domain. Counts from
     /// similar instants in time will be grouped together.
+    ///
+    /// Both `span` and `resolution` must be non-zero. With a coarse
+    /// `TimeUnit`, such as `Seconds<u32>`, durations shorter than the unit are
+    /// truncated to zero and are rejected.
     pub fn new(
         m: u32,
         r: u32,
         n: u32,
-        span: Duration,
-        resolution: Duration,
+        span: Duration<U>,
+        resolution: Duration<U>,
     ) -> Result<Self, Error> {
+        Self::with_config(
+            m,
+            r,
+            n,
+            span,
+            resolution,
+            false,
+            #[cfg(feature = "streamstats")]
+            0,
+        )
+    }
+
+    fn with_config(
+        m: u32,
+        r: u32,
+        n: u32,
+        span: Duration<U>,
+        resolution: Duration<U>,
+        lazy: bool,
+        #[cfg(feature = "streamstats")] sample_capacity: usize,
+    ) -> Result<Self, Error> {
+        // a zero resolution would never fill the span, and a zero span would
+        // leave the heatmap without any windows
+        if U::as_nanos(resolution) == 0 || U::as_nanos(span) == 0 {
+            return Err(Error::InvalidConfig);
+        }
+        let empty = if lazy {
+            Some(Arc::new(Histogram::new(m, r, n)?))
+        } else {
+            None
+        };
         let mut slices = Vec::new();
-        let mut true_span = Duration::from_nanos(0);
+        let mut true_span = U::from_secs(0);
         while true_span < span {
-            slices.push(Histogram::new(m, r, n)?);
-            true_span += resolution;
+            if lazy {
+                slices.push(Slice::Lazy(RwLock::new(None)));
+            } else {
+                slices.push(Slice::Allocated(Arc::new(Histogram::new(m, r, n)?)));
+            }
+            true_span = U::add(true_span, resolution);
         }
         slices.shrink_to_fit();
-        let next_tick = AtomicInstant::now();
-        next_tick.fetch_add(resolution, Ordering::Relaxed);
+        let next_tick = U::atomic(U::forward(U::now(), resolution));
         Ok(Self {
+            #[cfg(feature = "streamstats")]
+            samples: if sample_capacity == 0 {
+                Vec::new()
+            } else {
+                (0..slices.len())
+                    .map(|_| Samples::new(sample_capacity, lazy))
+                    .collect()
+            },
+            #[cfg(feature = "streamstats")]
+            sample_capacity,
             slices,
+            empty,
             current: AtomicUsize::new(0),
             next_tick,
             resolution,
@@ -140,25 +454,6 @@ impl Heatmap {
         })
     }
 
-    /// Creates a `Builder` with the default values `m = 0`, `r = 10`, `n = 30`,
-    /// `span = 60s`, `resolution = 1s`.
-    ///
-    /// This would create a `Heatmap` with 61 total `Histogram`s, each with
-    /// 11264 buckets which can store values from 1 to 1_073_741_823 with
-    /// values 1 to 1023 being stored in buckets with a width of 1. Such a
-    /// `Heatmap` would be appropriate for latencies measured in nanoseconds
-    /// where the max expected latency is one second and reporting covers the
-    /// past minute.
-    pub fn builder() -> Builder {
-        Builder {
-            m: 0,
-            r: 10,
-            n: 30,
-            span: Duration::from_secs(60),
-            resolution: Duration::from_secs(1),
-        }
-    }
-
     /// Returns the number of windows stored in the `Heatmap`
     pub fn windows(&self) -> usize {
         self.slices.len()
@@ -170,12 +465,140 @@ impl Heatmap {
         self.summary.buckets()
     }
 
-    /// Increment a time-value pair by a specified count
-    pub fn increment(&self, time: Instant, value: u64, count: u32) {
+    /// Returns the total number of bytes used by the `Heatmap` for its
+    /// histograms. For a lazy heatmap, this only includes the windows which
+    /// are currently allocated.
+    pub fn memory_footprint(&self) -> usize {
+        let windows: usize = self
+            .slices
+            .iter()
+            .filter_map(|slice| slice.histogram())
+            .map(|histogram| histogram.memory_footprint())
+            .sum();
+        let empty = self
+            .empty
+            .as_ref()
+            .map(|histogram| histogram.memory_footprint())
+            .unwrap_or(0);
+        core::mem::size_of::<Self>()
+            + core::mem::size_of_val(&*self.slices)
+            + self.summary.memory_footprint()
+            + empty
+            + windows
+    }
+
+    /// Returns an iterator over the windows of the `Heatmap` as pairs of the
+    /// start of the window and the `Histogram` of counts recorded within it.
+    ///
+    /// The windows are always returned in chronological order, from the oldest
+    /// to the most recent, which makes this suitable for rendering or exporting
+    /// the heatmap.
+    pub fn slices(&self) -> impl Iterator<Item = (Instant<U>, Arc<Histogram>)> + '_ {
+        self.into_iter()
+            .map(|window| (window.start(), window.into_histogram()))
+    }
+
+    /// Returns the start of the oldest window in the `Heatmap`, which is the
+    /// earliest instant covered by its span, or `None` if the `Heatmap` has no
+    /// windows.
+    ///
+    /// As with iterating over the windows, this does not age out windows which
+    /// have passed, so the span only moves forward as the `Heatmap` is
+    /// incremented or its percentiles are read.
+    pub fn oldest(&self) -> Option<Instant<U>> {
+        if self.slices.is_empty() {
+            return None;
+        }
+        let span = U::mul_f64(self.resolution, self.slices.len() as f64);
+        Some(U::backward(U::load(&self.next_tick), span))
+    }
+
+    /// Returns the end of the newest window in the `Heatmap`, which is the
+    /// latest instant covered by its span, or `None` if the `Heatmap` has no
+    /// windows. See `oldest()` for details.
+    pub fn newest(&self) -> Option<Instant<U>> {
+        if self.slices.is_empty() {
+            return None;
+        }
+        Some(U::load(&self.next_tick))
+    }
+
+    /// Increment a time-value pair by a specified count.
+    ///
+    /// The count is recorded into the window which covers the provided
+    /// instant. Instants later than the newest window advance the `Heatmap`,
+    /// aging out the oldest windows. An `Error::OutOfRange` is returned, and
+    /// nothing is recorded, if the instant is older than the span retained by
+    /// the `Heatmap` or if the value is outside of the storable range. Callers
+    /// on the hot path which always record the current instant can ignore the
+    /// result.
+    pub fn increment(&self, time: Instant<U>, value: u64, count: u32) -> Result<(), Error> {
         self.tick(time);
-        if let Some(slice) = self.slices.get(self.current.load(Ordering::Relaxed)) {
-            let _ = slice.increment(value, count);
-            let _ = self.summary.increment(value, count);
+        let index = self.index(time).ok_or(Error::OutOfRange)?;
+        match self.slices.get(index) {
+            Some(Slice::Allocated(histogram)) => histogram.increment(value, count)?,
+            Some(Slice::Lazy(histogram)) => {
+                let allocated = histogram.read().as_ref().map(|h| h.increment(value, count));
+                match allocated {
+                    Some(result) => result?,
+                    None => {
+                        // the empty histogram only exists for lazy heatmaps
+                        let empty = self.empty.as_ref().unwrap();
+                        histogram
+                            .write()
+                            .get_or_insert_with(|| Arc::new(Histogram::clone(empty)))
+                            .increment(value, count)?
+                    }
+                }
+            }
+            None => return Err(Error::OutOfRange),
+        }
+        let _ = self.summary.increment(value, count);
+        #[cfg(feature = "streamstats")]
+        if let Some(samples) = self.samples.get(index) {
+            samples.insert(value, count, self.sample_capacity);
+        }
+        Ok(())
+    }
+
+    /// Internal function which returns the index of the window which covers
+    /// the provided instant, or `None` if the instant is older than the span
+    /// of the `Heatmap`.
+    fn index(&self, time: Instant<U>) -> Option<usize> {
+        let current = self.current.load(Ordering::Relaxed);
+        let start = U::backward(U::load(&self.next_tick), self.resolution);
+        if time >= start {
+            return Some(current);
+        }
+        // the number of windows before the current window
+        let elapsed = U::as_nanos(U::duration_since(start, time));
+        let resolution = U::as_nanos(self.resolution);
+        let offset = elapsed.div_ceil(resolution) as usize;
+        if offset >= self.slices.len() {
+            None
+        } else {
+            Some((current + self.slices.len() - offset) % self.slices.len())
+        }
+    }
+
+    /// Resets the `Heatmap` by clearing the counts for every window and the
+    /// summary, for example, when a configuration change invalidates the data
+    /// recorded so far. For a lazy heatmap, the windows are freed.
+    ///
+    /// Increments which race with the clear may be partially retained.
+    pub fn clear(&self) {
+        for slice in self.slices.iter() {
+            match slice {
+                Slice::Allocated(histogram) => histogram.clear(),
+                Slice::Lazy(histogram) => {
+                    histogram.write().take();
+                }
+            }
+        }
+        self.summary.clear();
+        #[cfg(feature = "streamstats")]
+        for samples in self.samples.iter() {
+            samples.clear();
         }
     }
 
@@ -188,52 +611,160 @@ impl Heatmap {
     /// 90th percentile that is higher than the 100th percentile depending on
     /// the timing of calls to this function and the distribution of your data.
     ///
-    /// Note: concurrent writes may also effect the value returned by this
-    /// function. Users needing better consistency should ensure that other
-    /// threads are not writing into the heatmap while this function is
-    /// in-progress.
+    /// Note: this function does not take any locks while reading. The counts
+    /// are read with relaxed atomic loads, so it never blocks concurrent
+    /// calls to `increment` and the result is only weakly consistent:
+    /// concurrent writes may be partially reflected in the value returned.
+    /// Users needing better consistency should ensure that other threads are
+    /// not writing into the heatmap while this function is in-progress, or
+    /// query a `clone()` of the heatmap. For a lazy heatmap, aging out windows
+    /// which have passed briefly locks those windows, exactly as it would
+    /// during an `increment`.
+    ///
+    /// Note: for a heatmap built with `Builder::samples()`, the percentile is
+    /// calculated from the raw samples retained for each window. The returned
+    /// `Bucket` then has `low() == high()`, which is the sampled value, and its
+    /// count is the estimated number of counts of that value across the span.
     pub fn percentile(&self, percentile: f64) -> Result<Bucket, Error> {
-        self.tick(Instant::now());
+        self.tick(U::now());
+
+        #[cfg(feature = "streamstats")]
+        if !self.samples.is_empty() {
+            return self.sampled_percentile(percentile);
+        }
+
         self.summary.percentile(percentile).map_err(Error::from)
     }
 
+    /// Internal function which calculates a percentile from the raw samples.
+    /// Each window's samples are weighted by the number of counts they stand
+    /// in for, so that busy windows are not under-represented once their
+    /// buffers have overflowed.
+    #[cfg(feature = "streamstats")]
+    fn sampled_percentile(&self, percentile: f64) -> Result<Bucket, Error> {
+        if !(0.0..=100.0).contains(&percentile) {
+            return Err(Error::InvalidPercentile);
+        }
+
+        let mut samples: Vec<(u64, f64)> = self.samples.iter().flat_map(|s| s.weighted()).collect();
+        if samples.is_empty() {
+            return Err(Error::Empty);
+        }
+        samples.sort_unstable_by_key(|(value, _)| *value);
+
+        let total: f64 = samples.iter().map(|(_, weight)| weight).sum();
+        let target = percentile / 100.0 * total;
+        let mut seen = 0.0;
+        let index = samples
+            .iter()
+            .position(|(_, weight)| {
+                seen += weight;
+                seen >= target
+            })
+            .unwrap_or(samples.len() - 1);
+        let value = samples[index].0;
+        let count: f64 = samples
+            .iter()
+            .filter(|(v, _)| *v == value)
+            .map(|(_, weight)| weight)
+            .sum();
+
+        Ok(Bucket::new(
+            value,
+            value,
+            count.round().min(u32::MAX as f64) as u32,
+        ))
+    }
+
+    /// Returns a `Histogram` with the counts from across the span of this
+    /// `Heatmap` minus the counts from across the span of the `other`
+    /// `Heatmap`, for example, to compare latency distributions before and
+    /// after a change. Buckets where `other` has the larger count are zero in
+    /// the result.
+    ///
+    /// An error is returned if the heatmaps have different bucket
+    /// configurations.
+    pub fn diff(&self, other: &Heatmap<U>) -> Result<Histogram, Error> {
+        if self.buckets() != other.buckets() {
+            return Err(Error::IncompatibleHeatmap);
+        }
+
+        let now = U::now();
+        self.tick(now);
+        other.tick(now);
+
+        let result = self.summary.clone();
+        result.clear();
+
+        for (a, b) in self.summary.into_iter().zip(&other.summary) {
+            if a.low() != b.low() || a.high() != b.high() {
+                return Err(Error::IncompatibleHeatmap);
+            }
+            let count = a.count().saturating_sub(b.count());
+            if count > 0 {
+                result.increment(a.low(), count)?;
+            }
+        }
+
+        Ok(result)
+    }
+
     // Internal function which handles reuse of older windows to store newer
     /// values.
-    fn tick(&self, time: Instant) {
+    fn tick(&self, time: Instant<U>) {
         loop {
-            let next_tick = self.next_tick.load(Ordering::Relaxed);
+            let next_tick = U::load(&self.next_tick);
             if time < next_tick {
                 return;
             } else {
-                self.next_tick.fetch_add(self.resolution, Ordering::Relaxed);
+                U::fetch_add(&self.next_tick, self.resolution);
                 self.current.fetch_add(1, Ordering::Relaxed);
                 if self.current.load(Ordering::Relaxed) >= self.slices.len() {
                     self.current.store(0, Ordering::Relaxed);
                 }
                 let current = self.current.load(Ordering::Relaxed);
-                if let Some(slice) = self.slices.get(current) {
-                    let _ = self.summary.subtract(slice);
-                    slice.clear();
+                match self.slices.get(current) {
+                    Some(Slice::Allocated(histogram)) => {
+                        let _ = self.summary.subtract(histogram);
+                        histogram.clear();
+                    }
+                    Some(Slice::Lazy(histogram)) => {
+                        if let Some(histogram) = histogram.write().take() {
+                            let _ = self.summary.subtract(&histogram);
+                        }
+                    }
+                    None => {}
+                }
+                #[cfg(feature = "streamstats")]
+                if let Some(samples) = self.samples.get(current) {
+                    samples.clear();
                 }
             }
         }
     }
 
     /// Internal function to return a `Window` from the `Heatmap`.
-    fn get_slice(&self, index: usize) -> Option<Window> {
-        if let Some(histogram) = self.slices.get(index) {
+    fn get_slice(&self, index: usize) -> Option<Window<'_, U>> {
+        if let Some(slice) = self.slices.get(index) {
+            // windows which are not allocated are reported as empty
+            let histogram = slice.histogram().or_else(|| self.empty.clone())?;
             let shift = if index > self.current.load(Ordering::Relaxed) {
-                self.resolution.mul_f64(
+                U::mul_f64(
+                    self.resolution,
                     (self.slices.len() + self.current.load(Ordering::Relaxed) - index) as f64,
                 )
             } else {
-                self.resolution
-                    .mul_f64((self.current.load(Ordering::Relaxed) - index) as f64)
+                U::mul_f64(
+                    self.resolution,
+                    (self.current.load(Ordering::Relaxed) - index) as f64,
+                )
             };
+            let stop = U::backward(U::load(&self.next_tick), shift);
             Some(Window {
-                start: self.next_tick.load(Ordering::Relaxed) - shift - self.resolution,
-                stop: self.next_tick.load(Ordering::Relaxed) - shift,
+                start: U::backward(stop, self.resolution),
+                stop,
                 histogram,
+                _heatmap: PhantomData,
             })
         } else {
             None
@@ -241,32 +772,64 @@ impl Heatmap {
     }
 }
 
-impl Clone for Heatmap {
+/// Cloning a `Heatmap` takes a snapshot of its current state, which can then be
+/// rendered or serialized without being affected by later writes to the
+/// original.
+///
+/// The snapshot is only weakly consistent. Each counter is read individually
+/// with relaxed ordering and no lock is held while copying, so increments which
+/// race with the clone may be partially reflected, for example, in a window but
+/// not yet in the summary. The snapshot keeps the time of the original, so the
+/// windows of the copy continue to age out as time moves forward.
+impl<U: TimeUnit> Clone for Heatmap<U> {
     fn clone(&self) -> Self {
-        let slices = self.slices.clone();
+        let slices = self
+            .slices
+            .iter()
+            .map(|slice| match slice {
+                Slice::Allocated(histogram) => {
+                    Slice::Allocated(Arc::new(Histogram::clone(histogram)))
+                }
+                Slice::Lazy(histogram) => Slice::Lazy(RwLock::new(
+                    histogram
+                        .read()
+                        .as_ref()
+                        .map(|histogram| Arc::new(Histogram::clone(histogram))),
+                )),
+            })
+            .collect();
+        let empty = self.empty.clone();
         let summary = self.summary.clone();
         let resolution = self.resolution;
         let current = AtomicUsize::new(self.current.load(Ordering::Relaxed));
-        let next_tick = AtomicInstant::new(self.next_tick.load(Ordering::Relaxed));
+        let next_tick = U::atomic(U::load(&self.next_tick));
+
+        #[cfg(feature = "streamstats")]
+        let samples = self.samples.clone();
 
         Heatmap {
             slices,
+            empty,
             current,
             next_tick,
             resolution,
             summary,
+            #[cfg(feature = "streamstats")]
+            samples,
+            #[cfg(feature = "streamstats")]
+            sample_capacity: self.sample_capacity,
         }
     }
 }
 
-pub struct Iter<'a> {
-    inner: &'a Heatmap,
+pub struct Iter<'a, U: TimeUnit = Nanoseconds<u64>> {
+    inner: &'a Heatmap<U>,
     index: usize,
     visited: usize,
 }
 
-impl<'a> Iter<'a> {
-    fn new(inner: &'a Heatmap) -> Iter<'a> {
+impl<'a, U: TimeUnit> Iter<'a, U> {
+    fn new(inner: &'a Heatmap<U>) -> Iter<'a, U> {
         let index = if inner.current.load(Ordering::Relaxed) < (inner.slices.len() - 1) {
             inner.current.load(Ordering::Relaxed) + 1
         } else {
@@ -280,10 +843,10 @@ impl<'a> Iter<'a> {
     }
 }
 
-impl<'a> Iterator for Iter<'a> {
-    type Item = Window<'a>;
+impl<'a, U: TimeUnit> Iterator for Iter<'a, U> {
+    type Item = Window<'a, U>;
 
-    fn next(&mut self) -> Option<Window<'a>> {
+    fn next(&mut self) -> Option<Window<'a, U>> {
         if self.visited >= self.inner.slices.len() {
             None
         } else {
@@ -298,9 +861,9 @@ impl<'a> Iterator for Iter<'a> {
     }
 }
 
-impl<'a> IntoIterator for &'a Heatmap {
-    type Item = Window<'a>;
-    type IntoIter = Iter<'a>;
+impl<'a, U: TimeUnit> IntoIterator for &'a Heatmap<U> {
+    type Item = Window<'a, U>;
+    type IntoIter = Iter<'a, U>;
 
     fn into_iter(self) -> Self::IntoIter {
         Iter::new(self)
@@ -310,17 +873,332 @@ impl<'a> IntoIterator for &'a Heatmap {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{Duration, Instant};
 
     #[test]
     fn age_out() {
         let heatmap =
             Heatmap::new(0, 4, 20, Duration::from_secs(1), Duration::from_millis(1)).unwrap();
         assert_eq!(heatmap.percentile(0.0).map(|v| v.high()), Err(Error::Empty));
-        heatmap.increment(Instant::now(), 1, 1);
+        heatmap.increment(Instant::now(), 1, 1).unwrap();
         assert_eq!(heatmap.percentile(0.0).map(|v| v.high()), Ok(1));
         std::thread::sleep(std::time::Duration::from_millis(100));
         assert_eq!(heatmap.percentile(0.0).map(|v| v.high()), Ok(1));
         std::thread::sleep(std::time::Duration::from_millis(2000));
         assert_eq!(heatmap.percentile(0.0).map(|v| v.high()), Err(Error::Empty));
     }
+
+    #[test]
+    fn slices() {
+        let heatmap =
+            Heatmap::new(0, 4, 20, Duration::from_secs(1), Duration::from_millis(100)).unwrap();
+        assert_eq!(heatmap.slices().count(), heatmap.windows());
+
+        heatmap.increment(Instant::now(), 1, 1).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(250));
+        heatmap.increment(Instant::now(), 2, 1).unwrap();
+
+        let slices: Vec<_> = heatmap.slices().collect();
+        for pair in slices.windows(2) {
+            assert!(pair[0].0 < pair[1].0);
+        }
+
+        // the most recent value is in the newest slice
+        let (_, newest) = slices.last().unwrap();
+        assert_eq!(newest.percentile(100.0).map(|b| b.high()), Ok(2));
+
+        let total: u32 = slices
+            .iter()
+            .flat_map(|(_, histogram)| histogram.into_iter())
+            .map(|bucket| bucket.count())
+            .sum();
+        assert_eq!(total, 2);
+    }
+
+    #[test]
+    fn oldest_newest() {
+        let heatmap =
+            Heatmap::new(0, 4, 20, Duration::from_secs(1), Duration::from_millis(100)).unwrap();
+        let oldest = heatmap.oldest().unwrap();
+        let newest = heatmap.newest().unwrap();
+        assert_eq!(newest - oldest, Duration::from_secs(1));
+
+        // the span matches the windows
+        let windows: Vec<_> = heatmap.into_iter().collect();
+        assert_eq!(windows.first().map(|w| w.start()), Some(oldest));
+        assert_eq!(windows.last().map(|w| w.stop()), Some(newest));
+
+        // the span moves forward with time
+        heatmap
+            .increment(newest + Duration::from_millis(250), 1, 1)
+            .unwrap();
+        assert!(heatmap.oldest().unwrap() > oldest);
+        assert_eq!(
+            heatmap.newest().unwrap() - heatmap.oldest().unwrap(),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn zero_durations() {
+        use rustcommon_time::Seconds;
+
+        assert_eq!(
+            Heatmap::new(0, 4, 20, Duration::from_secs(0), Duration::from_secs(1)).err(),
+            Some(Error::InvalidConfig)
+        );
+        assert_eq!(
+            Heatmap::new(0, 4, 20, Duration::from_secs(1), Duration::from_secs(0)).err(),
+            Some(Error::InvalidConfig)
+        );
+
+        // as with a sub-second resolution truncated by a coarse unit
+        let result = Builder::<Seconds<u32>>::new()
+            .resolution(rustcommon_time::Duration::<Seconds<u32>>::from_secs(0))
+            .build();
+        assert_eq!(result.err(), Some(Error::InvalidConfig));
+    }
+
+    #[test]
+    fn out_of_span() {
+        let heatmap =
+            Heatmap::new(0, 4, 20, Duration::from_secs(1), Duration::from_millis(100)).unwrap();
+        let now = Instant::now();
+
+        // instants within the span are recorded into the window covering them
+        heatmap.increment(now, 1, 1).unwrap();
+        heatmap
+            .increment(now - Duration::from_millis(500), 2, 1)
+            .unwrap();
+        assert_eq!(heatmap.percentile(100.0).map(|v| v.high()), Ok(2));
+        let (_, newest) = heatmap.slices().last().unwrap();
+        assert_eq!(newest.percentile(100.0).map(|b| b.high()), Ok(1));
+
+        // instants older than the span are rejected
+        assert_eq!(
+            heatmap.increment(now - Duration::from_secs(5), 3, 1),
+            Err(Error::OutOfRange)
+        );
+        assert_eq!(heatmap.percentile(100.0).map(|v| v.high()), Ok(2));
+
+        // instants in the future advance the heatmap
+        heatmap
+            .increment(now + Duration::from_secs(5), 3, 1)
+            .unwrap();
+        assert_eq!(heatmap.percentile(0.0).map(|v| v.high()), Ok(3));
+    }
+
+    #[test]
+    fn clear() {
+        let heatmap =
+            Heatmap::new(0, 4, 20, Duration::from_secs(1), Duration::from_millis(100)).unwrap();
+        heatmap.increment(Instant::now(), 1, 1).unwrap();
+        assert_eq!(heatmap.percentile(0.0).map(|v| v.high()), Ok(1));
+        heatmap.clear();
+        assert_eq!(heatmap.percentile(0.0).map(|v| v.high()), Err(Error::Empty));
+        let total: u32 = heatmap
+            .slices()
+            .flat_map(|(_, histogram)| histogram.into_iter().collect::<Vec<_>>())
+            .map(|bucket| bucket.count())
+            .sum();
+        assert_eq!(total, 0);
+
+        // the heatmap continues to record after being cleared
+        heatmap.increment(Instant::now(), 2, 1).unwrap();
+        assert_eq!(heatmap.percentile(0.0).map(|v| v.high()), Ok(2));
+    }
+
+    #[test]
+    fn seconds() {
+        use rustcommon_time::Seconds;
+
+        let heatmap = Builder::<Seconds<u32>>::new()
+            .maximum_value(1024)
+            .span(rustcommon_time::Duration::<Seconds<u32>>::from_secs(
+                3 * 86_400,
+            ))
+            .resolution(rustcommon_time::Duration::<Seconds<u32>>::from_secs(60))
+            .build()
+            .unwrap();
+        assert_eq!(heatmap.windows(), 4320);
+
+        heatmap
+            .increment(rustcommon_time::Instant::<Seconds<u32>>::now(), 7, 1)
+            .unwrap();
+        assert_eq!(heatmap.percentile(100.0).map(|b| b.high()), Ok(7));
+
+        // the oldest windows may start before the clock's epoch, in which case
+        // their start times saturate
+        let slices: Vec<_> = heatmap.slices().collect();
+        assert_eq!(slices.len(), 4320);
+        for pair in slices.windows(2) {
+            assert!(pair[0].0 <= pair[1].0);
+        }
+        let (newest, _) = slices[4319];
+        let (previous, _) = slices[4318];
+        assert_eq!(
+            newest - previous,
+            rustcommon_time::Duration::<Seconds<u32>>::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn lazy() {
+        let eager = Heatmap::builder()
+            .span(Duration::from_secs(1))
+            .resolution(Duration::from_millis(1))
+            .build()
+            .unwrap();
+        let heatmap = Heatmap::builder()
+            .span(Duration::from_secs(1))
+            .resolution(Duration::from_millis(1))
+            .lazy(true)
+            .build()
+            .unwrap();
+        assert_eq!(heatmap.windows(), eager.windows());
+
+        // only the summary and empty histograms are allocated up front
+        let idle = heatmap.memory_footprint();
+        assert!(idle * 100 < eager.memory_footprint());
+
+        // missing windows are reported as empty
+        assert_eq!(heatmap.percentile(0.0).map(|v| v.high()), Err(Error::Empty));
+        assert_eq!(heatmap.slices().count(), heatmap.windows());
+
+        heatmap.increment(Instant::now(), 1, 1).unwrap();
+        heatmap.increment(Instant::now(), 2, 1).unwrap();
+        assert!(heatmap.memory_footprint() > idle);
+        assert_eq!(heatmap.percentile(100.0).map(|v| v.high()), Ok(2));
+        let total: u32 = heatmap
+            .slices()
+            .flat_map(|(_, histogram)| histogram.into_iter().collect::<Vec<_>>())
+            .map(|bucket| bucket.count())
+            .sum();
+        assert_eq!(total, 2);
+
+        // windows are freed as they age out
+        std::thread::sleep(std::time::Duration::from_millis(1500));
+        assert_eq!(heatmap.percentile(0.0).map(|v| v.high()), Err(Error::Empty));
+        assert_eq!(heatmap.memory_footprint(), idle);
+    }
+
+    #[test]
+    fn snapshot() {
+        let heatmap =
+            Heatmap::new(0, 4, 20, Duration::from_secs(1), Duration::from_millis(1)).unwrap();
+        heatmap.increment(Instant::now(), 1, 1).unwrap();
+
+        let snapshot = heatmap.clone();
+        heatmap.increment(Instant::now(), 2, 1).unwrap();
+
+        // later writes to the original are not reflected in the snapshot
+        assert_eq!(snapshot.percentile(100.0).map(|v| v.high()), Ok(1));
+        assert_eq!(heatmap.percentile(100.0).map(|v| v.high()), Ok(2));
+        assert_eq!(snapshot.windows(), heatmap.windows());
+    }
+
+    #[test]
+    fn diff() {
+        let a = Heatmap::new(0, 4, 20, Duration::from_secs(1), Duration::from_millis(1)).unwrap();
+        let b = Heatmap::new(0, 4, 20, Duration::from_secs(1), Duration::from_millis(1)).unwrap();
+        a.increment(Instant::now(), 1, 3).unwrap();
+        a.increment(Instant::now(), 100, 1).unwrap();
+        b.increment(Instant::now(), 1, 1).unwrap();
+        b.increment(Instant::now(), 1000, 1).unwrap();
+
+        let diff = a.diff(&b).unwrap();
+        let counts: Vec<(u64, u32)> = diff
+            .into_iter()
+            .filter(|bucket| bucket.count() > 0)
+            .map(|bucket| (bucket.low(), bucket.count()))
+            .collect();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[0], (1, 2));
+        let p100 = diff.percentile(100.0).unwrap();
+        assert!(p100.low() <= 100 && p100.high() >= 100);
+
+        let c = Heatmap::new(0, 4, 10, Duration::from_secs(1), Duration::from_millis(1)).unwrap();
+        assert!(matches!(a.diff(&c), Err(Error::IncompatibleHeatmap)));
+    }
+
+    #[cfg(feature = "streamstats")]
+    #[test]
+    fn exact_percentile() {
+        // buckets above 2 are wide, so only the raw samples can give exact
+        // percentiles
+        let heatmap = Heatmap::builder()
+            .min_resolution(1)
+            .min_resolution_range(2)
+            .maximum_value(1024)
+            .samples(100)
+            .build()
+            .unwrap();
+        for value in 1..=100 {
+            heatmap.increment(Instant::now(), value, 1).unwrap();
+        }
+        assert_eq!(heatmap.percentile(50.0).map(|v| v.high()), Ok(50));
+        assert_eq!(heatmap.percentile(99.0).map(|v| v.high()), Ok(99));
+        assert_eq!(heatmap.percentile(100.0).map(|v| v.high()), Ok(100));
+        assert_eq!(
+            heatmap.percentile(101.0).map(|v| v.high()),
+            Err(Error::InvalidPercentile)
+        );
+
+        // a lazy heatmap allocates the samples with the window
+        let heatmap = Heatmap::builder()
+            .min_resolution(1)
+            .min_resolution_range(2)
+            .maximum_value(1024)
+            .samples(10)
+            .lazy(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            heatmap.percentile(50.0).map(|v| v.high()),
+            Err(Error::Empty)
+        );
+        for value in 1..=20 {
+            heatmap.increment(Instant::now(), value, 1).unwrap();
+        }
+        let clone = heatmap.clone();
+        heatmap.clear();
+        assert_eq!(
+            heatmap.percentile(50.0).map(|v| v.high()),
+            Err(Error::Empty)
+        );
+        assert_eq!(clone.percentile(100.0).map(|v| v.high()), Ok(20));
+    }
+
+    #[cfg(feature = "streamstats")]
+    #[test]
+    fn weighted_samples() {
+        let heatmap = Heatmap::builder()
+            .min_resolution(1)
+            .min_resolution_range(2)
+            .maximum_value(1024)
+            .span(Duration::from_secs(1))
+            .resolution(Duration::from_millis(1))
+            .samples(10)
+            .build()
+            .unwrap();
+        // both windows overflow and retain 10 samples, but the first stands in
+        // for far more counts than the second
+        heatmap.increment(Instant::now(), 1, 1000).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        heatmap.increment(Instant::now(), 100, 10).unwrap();
+        assert_eq!(heatmap.percentile(99.0).map(|v| v.high()), Ok(1));
+        assert_eq!(heatmap.percentile(100.0).map(|v| v.high()), Ok(100));
+        assert_eq!(heatmap.percentile(50.0).map(|v| v.count()), Ok(1000));
+
+        // without samples, the feature does not change the percentiles
+        let heatmap = Heatmap::builder()
+            .min_resolution(1)
+            .min_resolution_range(2)
+            .maximum_value(1024)
+            .build()
+            .unwrap();
+        heatmap.increment(Instant::now(), 100, 1).unwrap();
+        let bucket = heatmap.percentile(100.0).unwrap();
+        assert!(bucket.low() < 100 && bucket.high() >= 100);
+    }
 }
@@ -2,22 +2,39 @@
 // Licensed under the Apache License, Version 2.0
 // http://www.apache.org/licenses/LICENSE-2.0
 
+//! A `Heatmap` stores counts for timestamped values over a moving span of
+//! time, as a ring buffer of histograms.
+//!
+//! # Windows
+//! `Window::histogram()` returns a reference which borrows from the `Window`
+//! rather than from the `Heatmap`, as the histograms of lazy windows are freed
+//! when they age out. Code which kept that reference after the `Window`, such
+//! as `heatmap.into_iter().map(|w| w.histogram())`, no longer compiles and
+//! should use `Window::into_histogram()`, which returns a shared handle to the
+//! histogram, instead.
+//!
+//! # Features
+//! - `streamstats` - adds `Builder::samples()`, which retains a bounded
+//!   buffer of raw samples for each window so that `Heatmap::percentile()`
+//!   reports values from those samples rather than the nearest histogram
+//!   bucket. Heatmaps which are not built with `Builder::samples()` behave the
+//!   same with or without this feature.
+
 mod error;
 mod heatmap;
+mod unit;
 mod window;
 
-use core::sync::atomic::AtomicU64;
 use rustcommon_time::Nanoseconds;
 
-pub use self::heatmap::Heatmap;
+pub use self::heatmap::{Builder, Heatmap, Iter};
 pub use error::Error;
+pub use unit::TimeUnit;
 pub use window::Window;
 
 pub type Instant = rustcommon_time::Instant<Nanoseconds<u64>>;
 pub type Duration = rustcommon_time::Duration<Nanoseconds<u64>>;
 
-type AtomicInstant = rustcommon_time::Instant<Nanoseconds<AtomicU64>>;
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -27,7 +44,7 @@ mod tests {
         let heatmap =
             Heatmap::new(0, 4, 20, Duration::from_secs(1), Duration::from_millis(1)).unwrap();
         assert_eq!(heatmap.percentile(0.0).map(|v| v.high()), Err(Error::Empty));
-        heatmap.increment(Instant::now(), 1, 1);
+        heatmap.increment(Instant::now(), 1, 1).unwrap();
         assert_eq!(heatmap.percentile(0.0).map(|v| v.high()), Ok(1));
         std::thread::sleep(std::time::Duration::from_millis(100));
         assert_eq!(heatmap.percentile(0.0).map(|v| v.high()), Ok(1));
@@ -0,0 +1,107 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use rustcommon_time::{Duration, Instant, Nanoseconds, Seconds};
+
+/// A unit of time which can be used for the clock of a `Heatmap`.
+///
+/// By default, a `Heatmap` uses `Nanoseconds<u64>`. A heatmap which covers a
+/// long span of time with a coarse resolution, for example, several days with
+/// a resolution of one minute, can instead use `Seconds<u32>`, which reads a
+/// cheaper clock and halves the size of each timestamp.
+pub trait TimeUnit: Copy + Ord + Send + Sync + 'static {
+    /// The atomic representation of this unit.
+    type Atomic: Send + Sync;
+
+    /// Returns the current instant.
+    fn now() -> Instant<Self>;
+
+    /// Returns a duration of the provided number of seconds.
+    fn from_secs(secs: u32) -> Duration<Self>;
+
+    /// Returns the duration as nanoseconds.
+    fn as_nanos(duration: Duration<Self>) -> u64;
+
+    /// Returns the duration multiplied by the provided factor.
+    fn mul_f64(duration: Duration<Self>, rhs: f64) -> Duration<Self>;
+
+    /// Returns the sum of two durations.
+    fn add(a: Duration<Self>, b: Duration<Self>) -> Duration<Self>;
+
+    /// Returns the instant which is `duration` after `instant`.
+    fn forward(instant: Instant<Self>, duration: Duration<Self>) -> Instant<Self>;
+
+    /// Returns the instant which is `duration` before `instant`, saturating at
+    /// the epoch of the clock.
+    fn backward(instant: Instant<Self>, duration: Duration<Self>) -> Instant<Self>;
+
+    /// Returns the duration from `earlier` to `later`, saturating at zero.
+    fn duration_since(later: Instant<Self>, earlier: Instant<Self>) -> Duration<Self>;
+
+    /// Creates a new atomic instant.
+    fn atomic(instant: Instant<Self>) -> Instant<Self::Atomic>;
+
+    /// Loads the value of an atomic instant.
+    fn load(atomic: &Instant<Self::Atomic>) -> Instant<Self>;
+
+    /// Advances an atomic instant by the provided duration.
+    fn fetch_add(atomic: &Instant<Self::Atomic>, duration: Duration<Self>);
+}
+
+macro_rules! time_unit {
+    ($unit:ty, $atomic:ty, $secs:ty) => {
+        impl TimeUnit for $unit {
+            type Atomic = $atomic;
+
+            fn now() -> Instant<Self> {
+                Instant::<Self>::now()
+            }
+
+            fn from_secs(secs: u32) -> Duration<Self> {
+                Duration::<Self>::from_secs(secs as $secs)
+            }
+
+            fn as_nanos(duration: Duration<Self>) -> u64 {
+                duration.as_nanos()
+            }
+
+            fn mul_f64(duration: Duration<Self>, rhs: f64) -> Duration<Self> {
+                duration.mul_f64(rhs)
+            }
+
+            fn add(mut a: Duration<Self>, b: Duration<Self>) -> Duration<Self> {
+                a += b;
+                a
+            }
+
+            fn forward(instant: Instant<Self>, duration: Duration<Self>) -> Instant<Self> {
+                instant + duration
+            }
+
+            fn backward(instant: Instant<Self>, duration: Duration<Self>) -> Instant<Self> {
+                instant.saturating_sub(duration)
+            }
+
+            fn duration_since(later: Instant<Self>, earlier: Instant<Self>) -> Duration<Self> {
+                later.saturating_duration_since(earlier)
+            }
+
+            fn atomic(instant: Instant<Self>) -> Instant<Self::Atomic> {
+                Instant::<Self::Atomic>::new(instant)
+            }
+
+            fn load(atomic: &Instant<Self::Atomic>) -> Instant<Self> {
+                atomic.load(Ordering::Relaxed)
+            }
+
+            fn fetch_add(atomic: &Instant<Self::Atomic>, duration: Duration<Self>) {
+                atomic.fetch_add(duration, Ordering::Relaxed);
+            }
+        }
+    };
+}
+
+time_unit!(Nanoseconds<u64>, Nanoseconds<AtomicU64>, u64);
+time_unit!(Seconds<u32>, Seconds<AtomicU32>, u32);
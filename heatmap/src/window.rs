@@ -2,25 +2,45 @@
 // Licensed under the Apache License, Version 2.0
 // http://www.apache.org/licenses/LICENSE-2.0
 
+use crate::TimeUnit;
+use core::marker::PhantomData;
 use histogram::Histogram;
 use rustcommon_time::*;
+use std::sync::Arc;
 
-pub struct Window<'a> {
-    pub(crate) start: Instant<Nanoseconds<u64>>,
-    pub(crate) stop: Instant<Nanoseconds<u64>>,
-    pub(crate) histogram: &'a Histogram,
+/// A single window of a `Heatmap`, which covers the span of time from `start()`
+/// to `stop()`.
+///
+/// The histogram of a window is shared with the `Heatmap`, as the histogram of
+/// a lazy window may be freed by the `Heatmap` once the window ages out. The
+/// reference returned by `histogram()` is therefore tied to the `Window`
+/// rather than to the `Heatmap`. Use `into_histogram()` to keep the histogram
+/// after the `Window` is dropped.
+pub struct Window<'a, U: TimeUnit = Nanoseconds<u64>> {
+    pub(crate) start: Instant<U>,
+    pub(crate) stop: Instant<U>,
+    pub(crate) histogram: Arc<Histogram>,
+    pub(crate) _heatmap: PhantomData<&'a Histogram>,
 }
 
-impl<'a> Window<'a> {
-    pub fn start(&self) -> Instant<Nanoseconds<u64>> {
+impl<'a, U: TimeUnit> Window<'a, U> {
+    pub fn start(&self) -> Instant<U> {
         self.start
     }
 
-    pub fn stop(&self) -> Instant<Nanoseconds<u64>> {
+    pub fn stop(&self) -> Instant<U> {
         self.stop
     }
 
-    pub fn histogram(&self) -> &'a Histogram {
+    pub fn histogram(&self) -> &Histogram {
         &self.histogram
     }
+
+    /// Consumes the `Window` and returns its histogram, which remains valid
+    /// after the window ages out of the `Heatmap`. Note that an allocated
+    /// histogram is reused by the `Heatmap` for a later window, so its counts
+    /// may change once the window has aged out.
+    pub fn into_histogram(self) -> Arc<Histogram> {
+        self.histogram
+    }
 }
@@ -12,6 +12,12 @@ pub struct Bucket {
 }
 
 impl Bucket {
+    /// Create a `Bucket` which represents the values from `low` to `high`
+    /// (inclusive) with the provided count.
+    pub fn new(low: u64, high: u64, count: u32) -> Self {
+        Self { low, high, count }
+    }
+
     /// The lowest value represented by this `Bucket`.
     pub fn low(&self) -> u64 {
         self.low
@@ -22,6 +28,19 @@ impl Bucket {
         self.high
     }
 
+    /// The value which is reported for this `Bucket`, for example, when it is
+    /// returned for a percentile. This is the highest value represented by the
+    /// `Bucket`, so the reported value never understates a recorded value.
+    pub fn value(&self) -> u64 {
+        self.high
+    }
+
+    /// The number of distinct values represented by this `Bucket`. As both
+    /// `low` and `high` are inclusive, this is `high - low + 1`.
+    pub fn width(&self) -> u64 {
+        (self.high - self.low).saturating_add(1)
+    }
+
     /// The sum of the recorded counts which fall into this `Bucket`.
     pub fn count(&self) -> u32 {
         self.count
@@ -14,7 +14,9 @@ pub enum Error {
     /// The provided percentile is outside of the range 0.0 - 100.0 (inclusive)
     InvalidPercentile,
     #[error("value out of range")]
-    /// The provided value is outside of the storable range.
+    /// The provided value is outside of the storable range. This is returned
+    /// when recording a value, and when reading a percentile from a histogram
+    /// whose only samples were above its maximum value.
     OutOfRange,
     #[error("incompatible histogram")]
     /// The operation requires histograms with the same parameters.
@@ -5,6 +5,7 @@
 use crate::*;
 
 use core::sync::atomic::AtomicU32;
+use core::sync::atomic::AtomicU64;
 use core::sync::atomic::Ordering;
 
 /// A `Histogram` groups recorded values into buckets of similar values and
@@ -30,10 +31,20 @@ pub struct Histogram {
 
     // buckets of ranges that hold actual counts
     buckets: Box<[AtomicU32]>,
+    // count of values rejected for being above the maximum value
+    too_high: AtomicU64,
 }
 
 /// A `Builder` allows for constructing a `Histogram` with the desired
 /// configuration.
+///
+/// The memory used by the resulting `Histogram` is dominated by its buckets,
+/// each of which is a 32bit counter. The number of buckets grows linearly with
+/// the number of powers of two between the minimum resolution range and the
+/// maximum value, and exponentially with the precision given by the ratio of
+/// the minimum resolution range to the minimum resolution. Use
+/// `Histogram::memory_footprint()` to see the total size of a configuration.
+#[derive(Clone)]
 pub struct Builder {
     // minimum resolution parameter `M = 2^m`
     m: u32,
@@ -108,7 +119,14 @@ impl Histogram {
         let N = if n == 64 { u64::MAX } else { (1 << n) - 1 };
         let G: u64 = 1 << (r - m - 1);
 
-        let n_buckets = (n - r + 2) as u64 * G;
+        // reject configurations with more buckets than can be allocated
+        // instead of overflowing
+        let n_buckets = match ((n - r + 2) as u64).checked_mul(G).filter(|buckets| {
+            buckets.saturating_mul(core::mem::size_of::<AtomicU32>() as u64) <= isize::MAX as u64
+        }) {
+            Some(buckets) => buckets,
+            None => return Err(Error::InvalidConfig),
+        };
 
         let mut buckets = Vec::new();
         buckets.resize_with(n_buckets as usize, || AtomicU32::new(0));
@@ -122,6 +140,7 @@ impl Histogram {
             N,
             G,
             buckets: buckets.into_boxed_slice(),
+            too_high: AtomicU64::new(0),
         })
     }
 
@@ -136,21 +155,25 @@ impl Histogram {
         Builder { m: 0, r: 10, n: 30 }
     }
 
-    /// Resets the `Histogram` by zeroing out the count for every bucket.
+    /// Resets the `Histogram` by zeroing out the count for every bucket and
+    /// the count of out of range values.
     pub fn clear(&self) {
         for bucket in self.buckets.iter() {
             bucket.store(0, Ordering::Relaxed);
         }
+        self.too_high.store(0, Ordering::Relaxed);
     }
 
     /// Increment the histogram bucket corresponding to the provided `value` by
     /// the provided `count`.
     ///
-    /// This operation wraps on overflow.
+    /// This operation wraps on overflow. Values above the maximum value are
+    /// rejected, but are counted towards the `overflow_fraction()`.
     #[allow(clippy::result_unit_err)]
     pub fn increment(&self, value: u64, count: u32) -> Result<(), Error> {
         if value > self.N {
             // value too big
+            self.too_high.fetch_add(count as u64, Ordering::Relaxed);
             return Err(Error::OutOfRange);
         }
 
@@ -160,6 +183,86 @@ impl Histogram {
         Ok(())
     }
 
+    /// Increment the histogram by each of the provided `(value, count)` pairs,
+    /// for example, when importing data which was collected elsewhere.
+    ///
+    /// This is more efficient than calling `increment` for each pair as the
+    /// counts for pairs which fall into the same bucket are combined, so each
+    /// bucket is updated at most once and the buckets are updated in order.
+    ///
+    /// If any value is out of range, an error is returned and the buckets are
+    /// not modified. As with `increment()`, the counts for the values above
+    /// the maximum value are counted towards the `overflow_fraction()`.
+    ///
+    /// A bucket holds a 32bit count, so the combined count for each bucket is
+    /// saturated at `u32::MAX` rather than truncated. Adding that count to the
+    /// bucket wraps on overflow, as with `increment()`.
+    pub fn increment_bulk(&self, pairs: &[(u64, u64)]) -> Result<(), Error> {
+        let mut updates = Vec::with_capacity(pairs.len());
+        let mut too_high: u64 = 0;
+        for (value, count) in pairs {
+            if *value > self.N {
+                // value too big
+                too_high = too_high.saturating_add(*count);
+            } else {
+                updates.push((self.bucket_index(*value), *count));
+            }
+        }
+        if updates.len() < pairs.len() {
+            self.too_high.fetch_add(too_high, Ordering::Relaxed);
+            return Err(Error::OutOfRange);
+        }
+
+        updates.sort_unstable_by_key(|(index, _)| *index);
+
+        let mut updates = updates.into_iter();
+        if let Some((mut index, mut count)) = updates.next() {
+            for (next, next_count) in updates {
+                if next == index {
+                    count = count.saturating_add(next_count);
+                } else {
+                    let saturated = u32::try_from(count).unwrap_or(u32::MAX);
+                    self.buckets[index].fetch_add(saturated, Ordering::Relaxed);
+                    index = next;
+                    count = next_count;
+                }
+            }
+            let count = u32::try_from(count).unwrap_or(u32::MAX);
+            self.buckets[index].fetch_add(count, Ordering::Relaxed);
+        }
+
+        Ok(())
+    }
+
+    /// Increment the histogram bucket corresponding to the provided `value` by
+    /// the provided `count`, saturating at the maximum count a bucket can hold
+    /// rather than wrapping.
+    ///
+    /// Returns `Ok(true)` if the bucket saturated, meaning that some or all of
+    /// the provided `count` could not be recorded. Callers can use this to
+    /// detect that the histogram has become inaccurate, for example, because
+    /// the counts should be periodically cleared or exported more frequently.
+    ///
+    /// As with `increment()`, values above the maximum value are counted
+    /// towards the `overflow_fraction()`.
+    pub fn saturating_increment(&self, value: u64, count: u32) -> Result<bool, Error> {
+        if value > self.N {
+            // value too big
+            self.too_high.fetch_add(count as u64, Ordering::Relaxed);
+            return Err(Error::OutOfRange);
+        }
+
+        let index = self.bucket_index(value);
+        // the closure always returns `Some`, so this can never fail
+        let previous = self.buckets[index]
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+                Some(current.saturating_add(count))
+            })
+            .unwrap_or_else(|current| current);
+
+        Ok(previous.checked_add(count).is_none())
+    }
+
     /// Decrement the histogram bucket corresponding to the provided `value` by
     /// the provided `count`.
     ///
@@ -172,15 +275,23 @@ impl Histogram {
         }
 
         let index = self.bucket_index(value);
-        self.buckets[index].fetch_add(count, Ordering::Relaxed);
+        self.buckets[index].fetch_sub(count, Ordering::Relaxed);
 
         Ok(())
     }
 
     /// Retrieve the `Bucket` which corresponds to the provided percentile.
     ///
-    /// An error will be returned if the percentile is invalid or if there are
-    /// no samples in the `Histogram`.
+    /// The percentile is in the range 0.0 - 100.0 (inclusive), for example,
+    /// `50.0` for the median and `99.9` for the p999. Note that `0.5` is the
+    /// 0.5th percentile, not the median.
+    ///
+    /// An error will be returned if the percentile is outside of this range
+    /// (including NaN) or if there are no samples in the `Histogram`. When
+    /// there are no samples within range, but values above the maximum value
+    /// have been recorded, `Error::OutOfRange` is returned rather than
+    /// `Error::Empty`, as the percentile lies beyond the range of the
+    /// `Histogram`.
     ///
     /// Note: if you are reporting on multiple percentiles, it is more efficient
     /// to use the `percentiles` function to retrieve multiple percentiles in a
@@ -196,7 +307,7 @@ impl Histogram {
             .map(|v| v.load(Ordering::Relaxed) as u64)
             .sum();
         if total == 0 {
-            return Err(Error::Empty);
+            return Err(self.no_samples());
         }
 
         let mut threshold = (percentile * total as f64 / 100.0).ceil() as u64;
@@ -228,35 +339,140 @@ impl Histogram {
         // seen while walking the histogram. this may be necessary if there is a
         // concurrent modification that reduces the counts before we have a
         // chance to get to that bucket
+        if seen == 0 {
+            // the histogram was cleared concurrently
+            return Err(Error::Empty);
+        }
         Ok(self.get_bucket(max))
     }
 
+    /// Returns the percentile (0.0 - 100.0) at which the provided `value` sits,
+    /// which is the percentage of samples in buckets up to and including the
+    /// bucket containing `value`. This is the inverse of `percentile`, for
+    /// example, to find which percentile a latency objective corresponds to.
+    ///
+    /// As samples are only tracked by bucket, all values within the same bucket
+    /// have the same percentile.
+    ///
+    /// An error will be returned if the value is above the maximum value or if
+    /// there are no samples in the `Histogram`.
+    pub fn percentile_of_value(&self, value: u64) -> Result<f64, Error> {
+        if value > self.N {
+            return Err(Error::OutOfRange);
+        }
+
+        let index = self.bucket_index(value);
+        let mut total = 0;
+        let mut seen = 0;
+
+        for (id, count) in self
+            .buckets
+            .iter()
+            .map(|b| b.load(Ordering::Relaxed) as u64)
+            .enumerate()
+        {
+            total += count;
+            if id <= index {
+                seen += count;
+            }
+        }
+
+        if total == 0 {
+            return Err(Error::Empty);
+        }
+
+        Ok(100.0 * seen as f64 / total as f64)
+    }
+
+    /// Retrieve the `Bucket` which corresponds to the provided percentile of a
+    /// weighted distribution, where the weight of each bucket is given by the
+    /// provided function rather than by its count alone. For example, using
+    /// `|bucket| bucket.count() as u64 * bytes(bucket)` would find the latency
+    /// below which the given percentage of bytes were transferred.
+    ///
+    /// The weights are calculated from the buckets on each call and are not
+    /// stored, so no additional memory is used. The function may be called
+    /// more than once per bucket.
+    ///
+    /// An error will be returned if the percentile is invalid or if the total
+    /// weight of all buckets is zero.
+    pub fn weighted_percentile(
+        &self,
+        weights: impl Fn(&Bucket) -> u64,
+        percentile: f64,
+    ) -> Result<Bucket, Error> {
+        if !(0.0..=100.0).contains(&percentile) {
+            return Err(Error::InvalidPercentile);
+        }
+
+        // the weights are summed as u128, as weights such as the number of
+        // bytes in a bucket could overflow a u64 once summed
+        let total: u128 = self
+            .into_iter()
+            .map(|bucket| u128::from(weights(&bucket)))
+            .sum();
+        if total == 0 {
+            return Err(Error::Empty);
+        }
+
+        let mut threshold = (percentile * total as f64 / 100.0).ceil() as u128;
+        if threshold == 0 {
+            threshold += 1;
+        }
+
+        let mut seen: u128 = 0;
+        let mut max = None;
+
+        for bucket in self {
+            let weight = weights(&bucket);
+            seen += u128::from(weight);
+
+            if seen >= threshold {
+                return Ok(bucket);
+            }
+
+            if weight > 0 {
+                max = Some(bucket);
+            }
+        }
+
+        // as with `percentile`, a concurrent modification may reduce the total
+        // weight before reaching the threshold, so return the last bucket seen
+        // with a non-zero weight
+        max.ok_or(Error::Empty)
+    }
+
     /// Returns a set of percentiles in a single and efficient bulk operation.
     /// Note that the returned percentiles will be sorted from lowest to highest
     /// in the result, even if they do not appear in that order in the provided
     /// set of requested percentiles.
+    ///
+    /// As with `percentile`, each percentile is in the range 0.0 - 100.0
+    /// (inclusive) and an error is returned if any of them are outside of this
+    /// range, or if there are no samples within the range of the `Histogram`.
     pub fn percentiles(&self, percentiles: &[f64]) -> Result<Vec<Percentile>, Error> {
-        let mut percentiles = percentiles.to_owned();
-        percentiles.sort_by(|a, b| a.partial_cmp(b).unwrap());
-
-        for percentile in &percentiles {
+        // validate before sorting, as a NaN can't be ordered
+        for percentile in percentiles {
             if !(0.0..=100.0).contains(percentile) {
                 return Err(Error::InvalidPercentile);
             }
         }
 
+        let mut percentiles = percentiles.to_owned();
+        percentiles.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
         let total: u64 = self
             .buckets
             .iter()
             .map(|v| v.load(Ordering::Relaxed) as u64)
             .sum();
         if total == 0 {
-            return Err(Error::Empty);
+            return Err(self.no_samples());
         }
 
         let thresholds: Vec<u64> = percentiles
             .iter()
-            .map(|v| std::cmp::min(1, (v * total as f64 / 100.0).ceil() as u64))
+            .map(|v| std::cmp::max(1, (v * total as f64 / 100.0).ceil() as u64))
             .collect();
 
         let mut max = 0;
@@ -276,7 +492,7 @@ impl Histogram {
             }
 
             seen += count;
-            while seen > thresholds[threshold_idx] && threshold_idx < thresholds.len() {
+            while threshold_idx < thresholds.len() && seen >= thresholds[threshold_idx] {
                 result.push(Percentile {
                     percentile: percentiles[threshold_idx],
                     bucket: self.get_bucket(bucket_idx),
@@ -295,7 +511,7 @@ impl Histogram {
         // reduces the counts before we have a chance to get to that bucket
         while result.len() < percentiles.len() {
             // get the index within the percentiles vec
-            let idx = percentiles.len() - result.len() - 1;
+            let idx = result.len();
             result.push(Percentile {
                 percentile: percentiles[idx],
                 bucket: self.get_bucket(max),
@@ -305,6 +521,75 @@ impl Histogram {
         Ok(result)
     }
 
+    /// Internal function which returns the error for a percentile when there
+    /// are no samples within range, distinguishing a histogram which has only
+    /// seen values above the maximum value from one which is empty.
+    fn no_samples(&self) -> Error {
+        if self.too_high.load(Ordering::Relaxed) > 0 {
+            Error::OutOfRange
+        } else {
+            Error::Empty
+        }
+    }
+
+    /// Returns a `Summary` with the count, min, max, mean, and the p50, p90,
+    /// p99, and p999 of the `Histogram`.
+    ///
+    /// The counts are read once and all of the statistics are calculated from
+    /// that single read, which is more efficient than separate calls and keeps
+    /// the statistics consistent with each other in the presence of concurrent
+    /// writers. An error is returned if the `Histogram` is empty.
+    pub fn summary(&self) -> Result<Summary, Error> {
+        let counts: Vec<u64> = self
+            .buckets
+            .iter()
+            .map(|v| v.load(Ordering::Relaxed) as u64)
+            .collect();
+
+        let total: u64 = counts.iter().sum();
+        if total == 0 {
+            return Err(Error::Empty);
+        }
+
+        let thresholds = [50.0, 90.0, 99.0, 99.9]
+            .map(|p: f64| std::cmp::max(1, (p * total as f64 / 100.0).ceil() as u64));
+        let mut percentiles = [0; 4];
+
+        let mut min = None;
+        let mut max = 0;
+        let mut sum = 0.0;
+        let mut seen = 0;
+        let mut threshold_idx = 0;
+
+        for (idx, count) in counts.into_iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+
+            let value = self.high(idx);
+            min.get_or_insert(value);
+            max = value;
+            sum += value as f64 * count as f64;
+
+            seen += count;
+            while threshold_idx < thresholds.len() && seen >= thresholds[threshold_idx] {
+                percentiles[threshold_idx] = value;
+                threshold_idx += 1;
+            }
+        }
+
+        Ok(Summary {
+            count: total,
+            min: min.unwrap_or(0),
+            max,
+            mean: sum / total as f64,
+            p50: percentiles[0],
+            p90: percentiles[1],
+            p99: percentiles[2],
+            p999: percentiles[3],
+        })
+    }
+
     /// Merges counts from the other `Histogram` into this `Histogram`. Returns
     /// an error if there are differences in the configurations of both
     /// `Histogram`s.
@@ -323,6 +608,8 @@ impl Histogram {
         {
             self.buckets[idx].fetch_add(value, Ordering::Relaxed);
         }
+        self.too_high
+            .fetch_add(other.too_high.load(Ordering::Relaxed), Ordering::Relaxed);
 
         Ok(())
     }
@@ -343,14 +630,97 @@ impl Histogram {
         {
             self.buckets[idx].fetch_sub(value, Ordering::Relaxed);
         }
+        self.too_high
+            .fetch_sub(other.too_high.load(Ordering::Relaxed), Ordering::Relaxed);
 
         Ok(())
     }
 
+    /// Returns a new `Histogram` with the provided configuration, as in `new`,
+    /// which contains the counts from this `Histogram`. This allows a
+    /// `Histogram` with a maximum value or precision which turns out to be
+    /// unsuitable to be replaced without discarding the data recorded so far.
+    ///
+    /// Only the count of each bucket is known, not the values within it, so
+    /// the result is an approximation. The count of each bucket is recorded
+    /// into the bucket of the new `Histogram` which contains the upper edge of
+    /// the old bucket. Increasing the precision therefore does not spread the
+    /// existing counts across the new, narrower buckets. Buckets whose upper
+    /// edge is above the new maximum value are counted as out of range, along
+    /// with the values which were already out of range in this `Histogram`.
+    pub fn reconfigure(&self, m: u32, r: u32, n: u32) -> Result<Histogram, Error> {
+        let ret = Histogram::new(m, r, n)?;
+        let mut too_high = self.too_high.load(Ordering::Relaxed);
+
+        for (idx, count) in self
+            .buckets
+            .iter()
+            .map(|v| v.load(Ordering::Relaxed))
+            .enumerate()
+        {
+            if count == 0 {
+                continue;
+            }
+            let value = self.high(idx);
+            if value > ret.N {
+                too_high += count as u64;
+            } else {
+                ret.buckets[ret.bucket_index(value)].fetch_add(count, Ordering::Relaxed);
+            }
+        }
+        ret.too_high.store(too_high, Ordering::Relaxed);
+
+        Ok(ret)
+    }
+
+    /// Returns the fraction of all recorded values, in the range 0.0 - 1.0,
+    /// which were above the maximum value and so could not be stored.
+    ///
+    /// A non-zero result indicates that the `Histogram` is configured with a
+    /// maximum value which is too small for the data, and that its percentiles
+    /// under-report the upper tail. Returns `0.0` if nothing has been recorded.
+    pub fn overflow_fraction(&self) -> f64 {
+        let too_high = self.too_high.load(Ordering::Relaxed);
+        if too_high == 0 {
+            return 0.0;
+        }
+        let in_range: u64 = self
+            .buckets
+            .iter()
+            .map(|v| v.load(Ordering::Relaxed) as u64)
+            .sum();
+        too_high as f64 / (too_high + in_range) as f64
+    }
+
+    /// Returns the number of buckets in the `Histogram`.
     pub fn buckets(&self) -> usize {
         self.buckets.len()
     }
 
+    /// Returns the width of the smallest bucket in the `Histogram`, which is
+    /// `M = 2^m`.
+    pub fn min_resolution(&self) -> u64 {
+        self.M
+    }
+
+    /// Returns the maximum value that the minimum resolution extends to, which
+    /// is `R = 2^r - 1`.
+    pub fn min_resolution_range(&self) -> u64 {
+        self.R
+    }
+
+    /// Returns the maximum value that can be recorded into the `Histogram`,
+    /// which is `N = 2^n - 1`.
+    pub fn maximum_value(&self) -> u64 {
+        self.N
+    }
+
+    /// Returns the total number of bytes used by the `Histogram`, including
+    /// both the struct itself and the heap allocated bucket storage.
+    pub fn memory_footprint(&self) -> usize {
+        core::mem::size_of::<Self>() + core::mem::size_of_val(&*self.buckets)
+    }
+
     fn low(&self, idx: usize) -> u64 {
         let idx = idx as u64;
         let m = self.m as u64;
@@ -372,10 +742,12 @@ impl Histogram {
         let g = idx >> (self.r - self.m - 1);
         let b = idx - g * self.G + 1;
 
+        // the subtraction comes first as the upper edge of the last bucket may
+        // be `u64::MAX`, in which case the intermediate sum would overflow
         if g < 1 {
             (1 << m) * b - 1
         } else {
-            (1 << (r + g - 2)) + (1 << (m + g - 1)) * b - 1
+            (1 << (r + g - 2)) + ((1 << (m + g - 1)) * b - 1)
         }
     }
 
@@ -422,6 +794,8 @@ impl Clone for Histogram {
         {
             ret.buckets[id].store(value, Ordering::Relaxed)
         }
+        ret.too_high
+            .store(self.too_high.load(Ordering::Relaxed), Ordering::Relaxed);
         ret
     }
 }
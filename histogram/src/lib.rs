@@ -2,15 +2,27 @@
 // Licensed under the Apache License, Version 2.0
 // http://www.apache.org/licenses/LICENSE-2.0
 
+//! A histogram which groups values into buckets of configurable precision.
+//!
+//! # Percentiles
+//! Percentiles are always expressed in the range 0.0 - 100.0 (inclusive), as
+//! they are by the `heatmap` and `streamstats` crates. For example, the median
+//! is `50.0` and the p999 is `99.9`. A percentile outside of this range,
+//! including NaN, results in [`Error::InvalidPercentile`].
+
 mod bucket;
 mod error;
 mod histogram;
+mod moving;
 mod percentile;
+mod summary;
 
 pub use self::histogram::{Builder, Histogram};
 pub use bucket::Bucket;
 pub use error::Error;
+pub use moving::MovingHistogram;
 pub use percentile::Percentile;
+pub use summary::Summary;
 
 #[cfg(test)]
 mod tests {
@@ -35,6 +47,317 @@ mod tests {
         assert_eq!(histogram.buckets(), 3328);
     }
 
+    #[test]
+    fn config() {
+        let histogram = Histogram::new(0, 10, 30).unwrap();
+        assert_eq!(histogram.min_resolution(), 1);
+        assert_eq!(histogram.min_resolution_range(), 1023);
+        assert_eq!(histogram.maximum_value(), 1_073_741_823);
+        assert_eq!(histogram.buckets(), 11264);
+
+        let histogram = Histogram::new(3, 10, 20).unwrap();
+        assert_eq!(histogram.min_resolution(), 8);
+        assert_eq!(histogram.min_resolution_range(), 1023);
+        assert_eq!(histogram.maximum_value(), 1_048_575);
+
+        let histogram = Histogram::new(0, 10, 64).unwrap();
+        assert_eq!(histogram.maximum_value(), u64::MAX);
+    }
+
+    #[test]
+    fn percentile_of_value() {
+        let histogram = Histogram::new(0, 7, 10).unwrap();
+        assert_eq!(histogram.percentile_of_value(1), Err(Error::Empty));
+
+        for value in 1..=100 {
+            histogram.increment(value, 1).unwrap();
+        }
+        assert_eq!(histogram.percentile_of_value(0), Ok(0.0));
+        assert_eq!(histogram.percentile_of_value(1), Ok(1.0));
+        assert_eq!(histogram.percentile_of_value(50), Ok(50.0));
+        assert_eq!(histogram.percentile_of_value(100), Ok(100.0));
+        assert_eq!(histogram.percentile_of_value(1023), Ok(100.0));
+        assert_eq!(histogram.percentile_of_value(1024), Err(Error::OutOfRange));
+
+        // inverse of percentile for exact buckets
+        let bucket = histogram.percentile(90.0).unwrap();
+        assert_eq!(histogram.percentile_of_value(bucket.high()), Ok(90.0));
+    }
+
+    #[test]
+    // values near the top of the u64 range must not overflow
+    fn extreme_values() {
+        for (m, r) in [(0, 1), (0, 10), (5, 20), (62, 63)] {
+            let histogram = Histogram::new(m, r, 64).unwrap();
+            for value in [u64::MAX, u64::MAX - 1, 1 << 63, (1 << 63) - 1] {
+                histogram.clear();
+                histogram.increment(value, 1).unwrap();
+                let bucket = histogram.percentile(100.0).unwrap();
+                assert!(bucket.low() <= value && value <= bucket.high());
+            }
+            let last = histogram.into_iter().last().unwrap();
+            assert_eq!(last.high(), u64::MAX);
+        }
+
+        // configurations with too many buckets to allocate are rejected
+        assert_eq!(Histogram::new(0, 63, 64).err(), Some(Error::InvalidConfig));
+        assert_eq!(Histogram::new(0, 64, 64).err(), Some(Error::InvalidConfig));
+    }
+
+    #[test]
+    fn memory_footprint() {
+        let histogram = Histogram::new(0, 2, 10).unwrap();
+        assert_eq!(
+            histogram.memory_footprint(),
+            core::mem::size_of::<Histogram>() + 20 * core::mem::size_of::<u32>()
+        );
+
+        let histogram = Histogram::new(0, 10, 30).unwrap();
+        assert_eq!(
+            histogram.memory_footprint(),
+            core::mem::size_of::<Histogram>() + 11264 * core::mem::size_of::<u32>()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let histogram = Histogram::new(0, 2, 10).unwrap();
+
+        // no samples
+        assert_eq!(histogram.percentile(50.0).err(), Some(Error::Empty));
+        assert_eq!(histogram.percentiles(&[50.0]).err(), Some(Error::Empty));
+
+        // values above the maximum are rejected, so all of the samples are out
+        // of range
+        assert_eq!(histogram.increment(1024, 1), Err(Error::OutOfRange));
+        assert_eq!(histogram.increment(u64::MAX, 1), Err(Error::OutOfRange));
+        assert_eq!(histogram.percentile(50.0).err(), Some(Error::OutOfRange));
+        assert_eq!(
+            histogram.percentiles(&[50.0]).err(),
+            Some(Error::OutOfRange)
+        );
+
+        // the maximum value itself is in range
+        assert_eq!(histogram.increment(1023, 1), Ok(()));
+        assert_eq!(histogram.percentile(100.0).map(|b| b.high()), Ok(1023));
+
+        // invalid percentiles are reported regardless of the contents
+        assert_eq!(
+            histogram.percentile(-1.0).err(),
+            Some(Error::InvalidPercentile)
+        );
+        assert_eq!(
+            histogram.percentile(100.1).err(),
+            Some(Error::InvalidPercentile)
+        );
+        assert_eq!(
+            histogram.percentile(f64::NAN).err(),
+            Some(Error::InvalidPercentile)
+        );
+
+        histogram.clear();
+        assert_eq!(histogram.percentile(50.0).err(), Some(Error::Empty));
+    }
+
+    #[test]
+    fn increment_bulk() {
+        let bulk = Histogram::new(0, 2, 10).unwrap();
+        let single = Histogram::new(0, 2, 10).unwrap();
+
+        let pairs = [(1, 3), (100, 2), (1, 4), (0, 1), (1023, 5), (101, 1)];
+        bulk.increment_bulk(&pairs).unwrap();
+        for (value, count) in pairs {
+            single.increment(value, count as u32).unwrap();
+        }
+
+        for (a, b) in bulk.into_iter().zip(&single) {
+            assert_eq!(a.count(), b.count());
+        }
+        assert_eq!(bulk.percentile(100.0).map(|b| b.high()), Ok(1023));
+
+        // an out of range value leaves the buckets unmodified, so only the
+        // rejected count remains
+        let histogram = Histogram::new(0, 2, 10).unwrap();
+        assert_eq!(
+            histogram.increment_bulk(&[(1, 1), (1024, 1)]),
+            Err(Error::OutOfRange)
+        );
+        assert_eq!(histogram.percentile(100.0).err(), Some(Error::OutOfRange));
+
+        assert_eq!(histogram.increment_bulk(&[]), Ok(()));
+
+        // counts which don't fit in a bucket saturate rather than truncate
+        let histogram = Histogram::new(0, 2, 10).unwrap();
+        histogram.increment_bulk(&[(1, 1 << 32)]).unwrap();
+        assert_eq!(histogram.percentile(100.0).map(|b| b.count()), Ok(u32::MAX));
+        let histogram = Histogram::new(0, 2, 10).unwrap();
+        histogram
+            .increment_bulk(&[(1, u64::MAX), (1, u64::MAX)])
+            .unwrap();
+        assert_eq!(histogram.percentile(100.0).map(|b| b.count()), Ok(u32::MAX));
+    }
+
+    #[test]
+    fn bucket_bounds() {
+        let histogram = Histogram::new(0, 2, 10).unwrap();
+        let mut next_low = 0;
+        for bucket in &histogram {
+            // buckets are contiguous and inclusive of both bounds
+            assert_eq!(bucket.low(), next_low);
+            assert!(bucket.low() <= bucket.high());
+            assert_eq!(bucket.width(), bucket.high() - bucket.low() + 1);
+            assert_eq!(bucket.value(), bucket.high());
+            next_low = bucket.high() + 1;
+        }
+        assert_eq!(next_low, 1024);
+
+        // a value is recorded into the bucket whose bounds contain it
+        histogram.increment(1000, 1).unwrap();
+        let bucket = histogram.percentile(100.0).unwrap();
+        assert!(bucket.low() <= 1000 && 1000 <= bucket.high());
+        assert_eq!(bucket.width(), 256);
+
+        let bucket = Bucket::new(0, u64::MAX, 0);
+        assert_eq!(bucket.width(), u64::MAX);
+    }
+
+    #[test]
+    fn decrement() {
+        let histogram = Histogram::new(0, 2, 10).unwrap();
+        histogram.increment(1, 3).unwrap();
+        histogram.increment(2, 1).unwrap();
+        histogram.decrement(2, 1).unwrap();
+        assert_eq!(histogram.percentile(100.0).map(|b| b.high()), Ok(1));
+        histogram.decrement(1, 3).unwrap();
+        assert_eq!(histogram.percentile(100.0).err(), Some(Error::Empty));
+    }
+
+    #[test]
+    fn moving_histogram() {
+        use rustcommon_time::{Duration, Instant, Nanoseconds};
+
+        let window = Duration::<Nanoseconds<u64>>::from_secs(10);
+        let histogram = MovingHistogram::new(0, 2, 10, Some(window), None).unwrap();
+        let start = Instant::<Nanoseconds<u64>>::now();
+
+        histogram.increment(start, 100, 1).unwrap();
+        histogram.increment(start + window, 1, 2).unwrap();
+        assert_eq!(histogram.len(), 2);
+        let bucket = histogram.percentile(100.0).unwrap();
+        assert!(bucket.low() <= 100 && 100 <= bucket.high());
+
+        // the first value ages out once it is older than the window
+        histogram.trim(start + window + Duration::<Nanoseconds<u64>>::from_nanos(1));
+        assert_eq!(histogram.len(), 1);
+        assert_eq!(histogram.percentile(100.0).map(|b| b.high()), Ok(1));
+
+        assert_eq!(histogram.increment(start, 1024, 1), Err(Error::OutOfRange));
+        assert_eq!(histogram.len(), 1);
+
+        // only the most recent values are kept with a capacity
+        let histogram = MovingHistogram::new(0, 2, 10, None, Some(2)).unwrap();
+        for value in [100, 1, 2] {
+            histogram.increment(start, value, 1).unwrap();
+        }
+        assert_eq!(histogram.len(), 2);
+        assert_eq!(histogram.percentile(100.0).map(|b| b.high()), Ok(2));
+
+        histogram.clear();
+        assert!(histogram.is_empty());
+        assert_eq!(histogram.percentile(0.0).err(), Some(Error::Empty));
+
+        // without a capacity, recording a value ages out the older values
+        let histogram = MovingHistogram::new(0, 2, 10, Some(window), None).unwrap();
+        histogram.increment(start, 100, 1).unwrap();
+        histogram.increment(start + window, 1, 1).unwrap();
+        assert_eq!(histogram.len(), 2);
+        histogram
+            .increment(
+                start + window + Duration::<Nanoseconds<u64>>::from_nanos(1),
+                2,
+                1,
+            )
+            .unwrap();
+        assert_eq!(histogram.len(), 2);
+    }
+
+    #[test]
+    fn saturating_increment() {
+        let histogram = Histogram::new(0, 2, 10).unwrap();
+        assert_eq!(histogram.saturating_increment(1, u32::MAX - 1), Ok(false));
+        assert_eq!(histogram.saturating_increment(1, 1), Ok(false));
+        assert_eq!(histogram.saturating_increment(1, 1), Ok(true));
+        assert_eq!(histogram.percentile(100.0).map(|b| b.count()), Ok(u32::MAX));
+        assert_eq!(histogram.saturating_increment(2, 1), Ok(false));
+        assert_eq!(
+            histogram.saturating_increment(2048, 1),
+            Err(Error::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn weighted_percentile() {
+        let histogram = Histogram::new(0, 2, 10).unwrap();
+        assert_eq!(
+            histogram
+                .weighted_percentile(|b| b.count().into(), 50.0)
+                .map(|b| b.high()),
+            Err(Error::Empty)
+        );
+
+        // 9 small values and 1 large value
+        histogram.increment(1, 9).unwrap();
+        histogram.increment(2, 1).unwrap();
+
+        // unweighted, the small value dominates
+        assert_eq!(
+            histogram
+                .weighted_percentile(|b| b.count().into(), 50.0)
+                .map(|b| b.high()),
+            Ok(1)
+        );
+        assert_eq!(histogram.percentile(50.0).map(|b| b.high()), Ok(1));
+
+        // weighting the large value by 100 makes it dominate
+        let weights = |b: &Bucket| {
+            let scale = if b.low() == 2 { 100 } else { 1 };
+            u64::from(b.count()) * scale
+        };
+        assert_eq!(
+            histogram
+                .weighted_percentile(weights, 50.0)
+                .map(|b| b.high()),
+            Ok(2)
+        );
+        assert_eq!(
+            histogram
+                .weighted_percentile(weights, 5.0)
+                .map(|b| b.high()),
+            Ok(1)
+        );
+        assert_eq!(
+            histogram
+                .weighted_percentile(weights, 101.0)
+                .map(|b| b.high()),
+            Err(Error::InvalidPercentile)
+        );
+
+        // weights which overflow a u64 once summed are still ordered
+        let weights = |b: &Bucket| if b.count() > 0 { u64::MAX / 2 + 1 } else { 0 };
+        assert_eq!(
+            histogram
+                .weighted_percentile(weights, 100.0)
+                .map(|b| b.high()),
+            Ok(2)
+        );
+        assert_eq!(
+            histogram
+                .weighted_percentile(weights, 50.0)
+                .map(|b| b.high()),
+            Ok(1)
+        );
+    }
+
     #[test]
     fn percentiles() {
         let histogram = Histogram::new(0, 2, 10).unwrap();
@@ -44,5 +367,133 @@ mod tests {
             assert!(histogram.percentile(100.0).map(|b| b.high()).unwrap_or(0) >= v);
             assert!(histogram.percentile(100.0).map(|b| b.low()).unwrap_or(0) <= v);
         }
+
+        // the bulk operation agrees with individual queries and is sorted
+        let requested = [99.9, 0.0, 50.0, 25.0, 100.0, 90.0];
+        let result = histogram.percentiles(&requested).unwrap();
+        assert_eq!(result.len(), requested.len());
+        for pair in result.windows(2) {
+            assert!(pair[0].percentile() <= pair[1].percentile());
+        }
+        for p in result {
+            assert_eq!(
+                Ok(p.bucket().high()),
+                histogram.percentile(p.percentile()).map(|b| b.high())
+            );
+        }
+    }
+
+    #[test]
+    fn summary() {
+        let histogram = Histogram::new(0, 2, 10).unwrap();
+        assert_eq!(histogram.summary().err(), Some(Error::Empty));
+
+        for v in 1..=1000 {
+            histogram.increment(v, 1).unwrap();
+        }
+        let summary = histogram.summary().unwrap();
+        assert_eq!(summary.count(), 1000);
+        assert_eq!(summary.min(), 1);
+        assert_eq!(summary.max(), 1023);
+        assert!(summary.mean() >= 500.5);
+
+        // the summary agrees with individual queries
+        for (value, percentile) in [
+            (summary.p50(), 50.0),
+            (summary.p90(), 90.0),
+            (summary.p99(), 99.0),
+            (summary.p999(), 99.9),
+        ] {
+            assert_eq!(
+                Ok(value),
+                histogram.percentile(percentile).map(|b| b.high())
+            );
+        }
+
+        let histogram = Histogram::new(0, 2, 10).unwrap();
+        histogram.increment(3, 4).unwrap();
+        let summary = histogram.summary().unwrap();
+        assert_eq!(
+            summary.to_string(),
+            "count: 4 min: 3 max: 3 mean: 3.00 p50: 3 p90: 3 p99: 3 p999: 3"
+        );
+    }
+
+    #[test]
+    fn overflow_fraction() {
+        let histogram = Histogram::new(0, 2, 10).unwrap();
+        assert_eq!(histogram.overflow_fraction(), 0.0);
+
+        histogram.increment(1, 3).unwrap();
+        assert_eq!(histogram.overflow_fraction(), 0.0);
+
+        assert_eq!(histogram.increment(1024, 1), Err(Error::OutOfRange));
+        assert_eq!(histogram.overflow_fraction(), 0.25);
+
+        // rejected bulk imports are also counted
+        let bulk = Histogram::new(0, 2, 10).unwrap();
+        assert_eq!(
+            bulk.increment_bulk(&[(1, 3), (1024, 2), (u64::MAX, 3)]),
+            Err(Error::OutOfRange)
+        );
+        assert_eq!(bulk.overflow_fraction(), 1.0);
+        bulk.increment_bulk(&[(1, 5)]).unwrap();
+        assert_eq!(bulk.overflow_fraction(), 0.5);
+
+        // carried through clones and merges
+        let other = histogram.clone();
+        assert_eq!(other.overflow_fraction(), 0.25);
+        histogram.merge(&other).unwrap();
+        assert_eq!(histogram.overflow_fraction(), 0.25);
+        histogram.subtract(&other).unwrap();
+        assert_eq!(histogram.overflow_fraction(), 0.25);
+
+        histogram.clear();
+        assert_eq!(histogram.overflow_fraction(), 0.0);
+    }
+
+    #[test]
+    fn reconfigure() {
+        let histogram = Histogram::new(0, 2, 10).unwrap();
+        histogram.increment(1, 3).unwrap();
+        histogram.increment(100, 2).unwrap();
+        histogram.increment(1000, 1).unwrap();
+        assert_eq!(histogram.increment(2000, 1), Err(Error::OutOfRange));
+
+        // a larger maximum keeps everything in range, but values which were
+        // already out of range remain so
+        let larger = histogram.reconfigure(0, 4, 12).unwrap();
+        assert_eq!(larger.maximum_value(), 4095);
+        assert_eq!(larger.percentile(0.0).map(|b| b.high()), Ok(1));
+        let bucket = larger.percentile(100.0).unwrap();
+        assert!(bucket.low() <= 1023 && 1023 <= bucket.high());
+        assert_eq!(larger.overflow_fraction(), 1.0 / 7.0);
+
+        // a smaller maximum counts the buckets above it as out of range
+        let smaller = histogram.reconfigure(0, 2, 8).unwrap();
+        assert_eq!(smaller.percentile(100.0).map(|b| b.high()), Ok(127));
+        assert_eq!(smaller.overflow_fraction(), 2.0 / 7.0);
+
+        assert_eq!(
+            histogram.reconfigure(2, 2, 8).err(),
+            Some(Error::InvalidConfig)
+        );
+    }
+
+    #[test]
+    fn invalid_percentiles() {
+        let histogram = Histogram::new(0, 2, 10).unwrap();
+        histogram.increment(1, 1).unwrap();
+
+        for percentile in [-1.0, 100.1, f64::NAN] {
+            assert_eq!(
+                histogram.percentile(percentile).map(|b| b.high()),
+                Err(Error::InvalidPercentile)
+            );
+            assert!(matches!(
+                histogram.percentiles(&[50.0, percentile]),
+                Err(Error::InvalidPercentile)
+            ));
+        }
     }
 }
@@ -0,0 +1,151 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use crate::*;
+
+use rustcommon_time::{Duration, Instant, Nanoseconds};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// A recorded value which is retained so that it can be removed from the
+/// histogram once it ages out.
+struct Sample {
+    time: Instant<Nanoseconds<u64>>,
+    value: u64,
+    count: u32,
+}
+
+/// A `MovingHistogram` is a `Histogram` which only reflects the values which
+/// were recorded within a moving window of time, and optionally, only the most
+/// recent values up to a capacity.
+///
+/// Each recorded value is retained alongside the histogram and is subtracted
+/// from it when it ages out or is displaced by a newer value. Recording and
+/// trimming are serialized by a lock, so a value is never removed before it
+/// has been added. This makes a `MovingHistogram` more expensive to record
+/// into than a `Histogram`, with memory proportional to the number of values
+/// retained. For high rates of values, a `Heatmap`, which ages out whole
+/// windows at once, is often a better fit.
+///
+/// Values age out relative to the current time when reading percentiles, so
+/// the times provided to `increment()` should come from `Instant::now()` or
+/// `Instant::recent()`.
+pub struct MovingHistogram {
+    histogram: Histogram,
+    window: Option<Duration<Nanoseconds<u64>>>,
+    capacity: Option<usize>,
+    samples: Mutex<VecDeque<Sample>>,
+}
+
+impl MovingHistogram {
+    /// Create a new `MovingHistogram`. The parameters `m`, `r`, and `n` have
+    /// the same meaning as for `Histogram::new()`.
+    ///
+    /// - `window` - if provided, values are removed once they are older than
+    ///   this duration.
+    ///
+    /// - `capacity` - if provided, only this many of the most recently
+    ///   recorded values are retained.
+    pub fn new(
+        m: u32,
+        r: u32,
+        n: u32,
+        window: Option<Duration<Nanoseconds<u64>>>,
+        capacity: Option<usize>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            histogram: Histogram::new(m, r, n)?,
+            window,
+            capacity,
+            samples: Mutex::new(VecDeque::new()),
+        })
+    }
+
+    /// Record a value which occurred at the provided time with the provided
+    /// count. Values which are older than the window as of the provided time,
+    /// and older values which no longer fit within the capacity, are removed.
+    ///
+    /// The time should come from `Instant::now()` or `Instant::recent()`, see
+    /// the type-level documentation.
+    pub fn increment(
+        &self,
+        time: Instant<Nanoseconds<u64>>,
+        value: u64,
+        count: u32,
+    ) -> Result<(), Error> {
+        let mut samples = self.samples.lock().unwrap();
+        self.histogram.increment(value, count)?;
+        samples.push_back(Sample { time, value, count });
+        self.trim_locked(&mut samples, time);
+        if let Some(capacity) = self.capacity {
+            while samples.len() > capacity {
+                self.remove_oldest(&mut samples);
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the values which are older than the window as of the provided
+    /// time. This is called automatically when recording values and when
+    /// reading percentiles, but may also be called periodically to release
+    /// the memory used by values which have aged out while no values were
+    /// recorded.
+    pub fn trim(&self, now: Instant<Nanoseconds<u64>>) {
+        let mut samples = self.samples.lock().unwrap();
+        self.trim_locked(&mut samples, now);
+    }
+
+    /// Retrieve the `Bucket` which corresponds to the provided percentile of
+    /// the values within the window as of `Instant::now()`. See
+    /// `Histogram::percentile()`.
+    pub fn percentile(&self, percentile: f64) -> Result<Bucket, Error> {
+        self.trim(Instant::<Nanoseconds<u64>>::now());
+        self.histogram.percentile(percentile)
+    }
+
+    /// Retrieve the `Percentile`s for the provided percentiles of the values
+    /// within the window as of `Instant::now()`. See
+    /// `Histogram::percentiles()`.
+    pub fn percentiles(&self, percentiles: &[f64]) -> Result<Vec<Percentile>, Error> {
+        self.trim(Instant::<Nanoseconds<u64>>::now());
+        self.histogram.percentiles(percentiles)
+    }
+
+    /// Remove all values.
+    pub fn clear(&self) {
+        let mut samples = self.samples.lock().unwrap();
+        samples.clear();
+        self.histogram.clear();
+    }
+
+    /// Returns the number of values which are currently retained.
+    pub fn len(&self) -> usize {
+        self.samples.lock().unwrap().len()
+    }
+
+    /// Returns `true` if no values are retained.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn trim_locked(&self, samples: &mut VecDeque<Sample>, now: Instant<Nanoseconds<u64>>) {
+        let window = match self.window {
+            Some(window) => window,
+            None => return,
+        };
+        while let Some(oldest) = samples.front() {
+            if now.saturating_duration_since(oldest.time) <= window {
+                break;
+            }
+            self.remove_oldest(samples);
+        }
+    }
+
+    fn remove_oldest(&self, samples: &mut VecDeque<Sample>) {
+        if let Some(sample) = samples.pop_front() {
+            // the value was in range when it was recorded
+            let _ = self.histogram.decrement(sample.value, sample.count);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+/// A `Summary` bundles the statistics which are commonly reported for a
+/// histogram, calculated together by `Histogram::summary`.
+///
+/// As with `Bucket::value`, each value is the upper edge of the bucket that
+/// contains it, so it is never lower than the true value. The mean is
+/// calculated from these values as well.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Summary {
+    pub(crate) count: u64,
+    pub(crate) min: u64,
+    pub(crate) max: u64,
+    pub(crate) mean: f64,
+    pub(crate) p50: u64,
+    pub(crate) p90: u64,
+    pub(crate) p99: u64,
+    pub(crate) p999: u64,
+}
+
+impl Summary {
+    /// Returns the total count of samples.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the smallest recorded value.
+    pub fn min(&self) -> u64 {
+        self.min
+    }
+
+    /// Returns the largest recorded value.
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Returns the mean of the recorded values.
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Returns the 50th percentile.
+    pub fn p50(&self) -> u64 {
+        self.p50
+    }
+
+    /// Returns the 90th percentile.
+    pub fn p90(&self) -> u64 {
+        self.p90
+    }
+
+    /// Returns the 99th percentile.
+    pub fn p99(&self) -> u64 {
+        self.p99
+    }
+
+    /// Returns the 99.9th percentile.
+    pub fn p999(&self) -> u64 {
+        self.p999
+    }
+}
+
+impl core::fmt::Display for Summary {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "count: {} min: {} max: {} mean: {:.2} p50: {} p90: {} p99: {} p999: {}",
+            self.count, self.min, self.max, self.mean, self.p50, self.p90, self.p99, self.p999
+        )
+    }
+}
@@ -17,10 +17,10 @@ pub fn default_format(
     now: DateTime,
     record: &Record,
 ) -> Result<(), std::io::Error> {
+    now.write_rfc3339_opts(w, SecondsFormat::Millis, false)?;
     writeln!(
         w,
-        "{} {} [{}] {}",
-        now.to_rfc3339_opts(SecondsFormat::Millis, false),
+        " {} [{}] {}",
         record.level(),
         record.module_path().unwrap_or("<unnamed>"),
         record.args()
@@ -32,10 +32,6 @@ pub fn klog_format(
     now: DateTime,
     record: &Record,
 ) -> Result<(), std::io::Error> {
-    writeln!(
-        w,
-        "{} {}",
-        now.to_rfc3339_opts(SecondsFormat::Millis, false),
-        record.args()
-    )
+    now.write_rfc3339_opts(w, SecondsFormat::Millis, false)?;
+    writeln!(w, " {}", record.args())
 }
@@ -16,6 +16,11 @@
 //! an `AsyncLog` which has low overhead, but directs log messages to a single
 //! `Output`.
 //!
+//! A `LogBuilder` can also construct a `BinaryLog`, which writes raw byte
+//! records to a single `Output` without formatting. This allows binary records,
+//! such as length-prefixed audit entries, to share the same asynchronous
+//! infrastructure as text logs.
+//!
 //! A `SamplingLogBuilder` can be used to construct an `AsyncLog` which will
 //! filter the log messages using sampling before directing the log messages to
 //! a single `Output`.
@@ -26,7 +31,19 @@
 //! be routed to that instance of `AsyncLog`. Log messages that do not match any
 //! specific target will be routed to the default `AsyncLog` that has been added
 //! to the `MultiLogBuilder`. If there is no default, messages that do not match
-//! any specific target will be simply dropped.
+//! any specific target will be simply dropped. Additionally, messages can be
+//! routed based on their level, for example, to send all errors to a dedicated
+//! output. Level routes are applied in addition to the target routing, so such
+//! messages will also be sent to the `AsyncLog` for their target.
+//!
+//! With the `syslog` feature enabled, a `SyslogOutput` can be used to send
+//! log messages to the local syslog daemon or to a remote syslog server. It
+//! should be paired with the `syslog_format` format function so that the
+//! level of each record is mapped to a syslog severity.
+//!
+//! Panics normally bypass the logger and are written directly to stderr. By
+//! starting the `AsyncLog` with `start_with_panic_hook`, panics are instead
+//! logged at `ERROR` level and the drain is flushed before the panic proceeds.
 //!
 //! This combination of logging types allows us to compose a logging backend
 //! which meets the application's needs. For example, you can use a local log
@@ -40,16 +57,22 @@ mod format;
 mod multi;
 mod nop;
 mod outputs;
+mod panic;
 mod sampling;
 mod single;
+#[cfg(feature = "syslog")]
+mod syslog;
 mod traits;
 
 pub use format::*;
 pub use multi::*;
 pub use nop::*;
 pub use outputs::*;
+pub use panic::*;
 pub use sampling::*;
 pub use single::*;
+#[cfg(feature = "syslog")]
+pub use syslog::*;
 pub use traits::*;
 
 use mpmc::Queue;
@@ -92,6 +115,10 @@ counter!(
     "number of log messages dropped due to full queues"
 );
 counter!(LOG_DROP_BYTE, "number of bytes dropped due to full queues");
+gauge!(
+    LOG_QUEUE_DEPTH,
+    "current number of log messages waiting in queues to be written"
+);
 counter!(
     LOG_FLUSH,
     "number of times logging destinations have been flushed"
@@ -10,6 +10,7 @@ use ahash::AHashMap as HashMap;
 pub(crate) struct MultiLogger {
     default: Option<Box<dyn Log>>,
     targets: HashMap<String, Box<dyn Log>>,
+    levels: Vec<(Level, Box<dyn Log>)>,
     level_filter: LevelFilter,
 }
 
@@ -20,12 +21,24 @@ impl MultiLogger {
             .map(|t| t.as_ref())
             .or_else(|| self.default.as_deref())
     }
+
+    fn get_levels(&self, level: Level) -> impl Iterator<Item = &dyn Log> {
+        self.levels
+            .iter()
+            .filter(move |(l, _)| level <= *l)
+            .map(|(_, log)| log.as_ref())
+    }
 }
 
 impl Log for MultiLogger {
     fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
         if metadata.level() > self.level_filter {
             false
+        } else if self
+            .get_levels(metadata.level())
+            .any(|log| log.enabled(metadata))
+        {
+            true
         } else if let Some(target) = self.get_target(metadata.target()) {
             target.enabled(metadata)
         } else {
@@ -37,6 +50,11 @@ impl Log for MultiLogger {
         if record.metadata().level() > self.level_filter {
             return;
         }
+        for log in self.get_levels(record.metadata().level()) {
+            if log.enabled(record.metadata()) {
+                log.log(record)
+            }
+        }
         if let Some(target) = self.get_target(record.target()) {
             if target.enabled(record.metadata()) {
                 target.log(record)
@@ -50,6 +68,7 @@ impl Log for MultiLogger {
 pub(crate) struct MultiLogDrain {
     default: Option<Box<dyn Drain>>,
     targets: HashMap<String, Box<dyn Drain>>,
+    levels: Vec<Box<dyn Drain>>,
 }
 
 impl Drain for MultiLogDrain {
@@ -60,6 +79,9 @@ impl Drain for MultiLogDrain {
         for (_target, log_handle) in self.targets.iter_mut() {
             log_handle.flush()?;
         }
+        for log_handle in self.levels.iter_mut() {
+            log_handle.flush()?;
+        }
         Ok(())
     }
 }
@@ -68,9 +90,15 @@ impl Drain for MultiLogDrain {
 /// on the log's `target` metadata to a corresponding `AsyncLog`. Targets which
 /// do not match a specific target will be routed to the default `AsyncLog` if
 /// one is configured.
+///
+/// Messages may additionally be routed based on their level. Level routes do
+/// not take precedence over target routes, they are applied in addition to
+/// them. A message which matches a level route is sent to that `AsyncLog` and
+/// is still sent to the `AsyncLog` for its target (or the default).
 pub struct MultiLogBuilder {
     default: Option<AsyncLog>,
     targets: HashMap<String, AsyncLog>,
+    levels: Vec<(Level, AsyncLog)>,
     level_filter: LevelFilter,
 }
 
@@ -79,6 +107,7 @@ impl Default for MultiLogBuilder {
         Self {
             default: None,
             targets: HashMap::new(),
+            levels: Vec::new(),
             level_filter: LevelFilter::Trace,
         }
     }
@@ -100,6 +129,15 @@ impl MultiLogBuilder {
         self
     }
 
+    /// Adds a route for all messages at or above the severity of the provided
+    /// `level` regardless of their target. For example, a route for
+    /// `Level::Warn` will receive both warnings and errors. Messages are still
+    /// routed to the `AsyncLog` for their target (or the default) as well.
+    pub fn add_level(mut self, level: Level, log: AsyncLog) -> Self {
+        self.levels.push((level, log));
+        self
+    }
+
     pub fn level_filter(mut self, level_filter: LevelFilter) -> Self {
         self.level_filter = level_filter;
         self
@@ -109,12 +147,14 @@ impl MultiLogBuilder {
         let mut loggers = MultiLogger {
             default: None,
             targets: HashMap::new(),
+            levels: Vec::new(),
             level_filter: self.level_filter,
         };
 
         let mut drains = MultiLogDrain {
             default: None,
             targets: HashMap::new(),
+            levels: Vec::new(),
         };
 
         if let Some(log) = self.default.take() {
@@ -127,6 +167,11 @@ impl MultiLogBuilder {
             drains.targets.insert(name.to_owned(), log.drain);
         }
 
+        for (level, log) in self.levels.drain(..) {
+            loggers.levels.push((level, log.logger));
+            drains.levels.push(log.drain);
+        }
+
         AsyncLog {
             logger: Box::new(loggers),
             drain: Box::new(drains),
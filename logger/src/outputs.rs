@@ -4,8 +4,9 @@
 
 use crate::*;
 
-use std::io::{BufWriter, Error, Write};
+use std::io::{BufWriter, Error, ErrorKind, Write};
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
 
 /// An output that writes to `stdout`.
 pub struct Stdout {
@@ -67,6 +68,81 @@ impl Write for Stderr {
 
 impl Output for Stderr {}
 
+/// An output that discards all bytes written to it.
+///
+/// Unlike the `NopLogBuilder`, which disables logging entirely, a logger using
+/// this output still formats, queues, and drains every message, and the
+/// logging metrics such as `LOG_WRITE`, `LOG_DROP`, and `LOG_FLUSH` are
+/// updated as usual. This allows the overhead of the logging pipeline to be
+/// measured independently of any I/O.
+#[derive(Default)]
+pub struct NullOutput {}
+
+impl NullOutput {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Write for NullOutput {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::result::Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Output for NullOutput {}
+
+/// An output that sends each formatted log line over a channel, for example,
+/// to capture logs in tests or to forward them to a custom sink.
+///
+/// Each message sent is a single line, including its trailing newline. Bytes
+/// which are not yet terminated by a newline are held until the next write or
+/// flush. Writes fail with `ErrorKind::BrokenPipe` once the receiver has been
+/// dropped.
+pub struct ChannelOutput {
+    sender: Sender<Vec<u8>>,
+    pending: Vec<u8>,
+}
+
+impl ChannelOutput {
+    pub fn new(sender: Sender<Vec<u8>>) -> Self {
+        Self {
+            sender,
+            pending: Vec::new(),
+        }
+    }
+
+    fn send(&mut self, line: Vec<u8>) -> Result<(), Error> {
+        self.sender
+            .send(line)
+            .map_err(|_| Error::new(ErrorKind::BrokenPipe, "log receiver disconnected"))
+    }
+}
+
+impl Write for ChannelOutput {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.pending.extend_from_slice(buf);
+        while let Some(position) = self.pending.iter().position(|b| *b == b'\n') {
+            let rest = self.pending.split_off(position + 1);
+            let line = std::mem::replace(&mut self.pending, rest);
+            self.send(line)?;
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::result::Result<(), Error> {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            self.send(line)?;
+        }
+        Ok(())
+    }
+}
+
+impl Output for ChannelOutput {}
+
 /// A file based output which allows rotating the current log file off to a
 /// backup location.
 pub struct File {
@@ -0,0 +1,90 @@
+// Copyright 2021 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use crate::*;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::io::Error;
+use std::panic::PanicHookInfo;
+use std::sync::{Arc, Mutex, TryLockError};
+
+/// A `Drain` which may be shared between the thread which periodically
+/// flushes it and the panic hook installed by `AsyncLog::start_with_panic_hook`.
+#[derive(Clone)]
+pub struct SharedDrain {
+    inner: Arc<Mutex<Box<dyn Drain>>>,
+}
+
+impl SharedDrain {
+    fn new(drain: Box<dyn Drain>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(drain)),
+        }
+    }
+
+    // Flushes the drain from within the panic hook. If the panic happened
+    // while this thread was flushing, the lock is already held and the flush
+    // is skipped rather than deadlocking.
+    fn flush_for_panic(&self) {
+        let mut drain = match self.inner.try_lock() {
+            Ok(drain) => drain,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) => return,
+        };
+        let _ = drain.flush();
+    }
+}
+
+impl Drain for SharedDrain {
+    fn flush(&mut self) -> Result<(), Error> {
+        let mut drain = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        drain.flush()
+    }
+}
+
+impl AsyncLog {
+    /// Register the logger, as with `start`, and additionally install a panic
+    /// hook which logs panics at `ERROR` level through this logger.
+    ///
+    /// The logged message includes the panic message, its location, and a
+    /// backtrace if backtraces are enabled through `RUST_BACKTRACE`. The drain
+    /// is flushed before the previously installed panic hook is called, so
+    /// the panic appears in the same output as all other log messages.
+    pub fn start_with_panic_hook(self) -> SharedDrain {
+        let drain = SharedDrain::new(self.start());
+        let hook_drain = drain.clone();
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            log_panic(info);
+            hook_drain.flush_for_panic();
+            previous(info);
+        }));
+        drain
+    }
+}
+
+fn log_panic(info: &PanicHookInfo<'_>) {
+    let message = if let Some(s) = info.payload().downcast_ref::<&str>() {
+        s
+    } else if let Some(s) = info.payload().downcast_ref::<String>() {
+        s.as_str()
+    } else {
+        "Box<dyn Any>"
+    };
+    let thread = std::thread::current();
+    let thread = thread.name().unwrap_or("<unnamed>");
+    let location = info
+        .location()
+        .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+        .unwrap_or_else(|| "<unknown>".to_string());
+
+    let backtrace = Backtrace::capture();
+    if backtrace.status() == BacktraceStatus::Captured {
+        error!(
+            "thread '{}' panicked at {}: {}\n{}",
+            thread, location, message, backtrace
+        );
+    } else {
+        error!("thread '{}' panicked at {}: {}", thread, location, message);
+    }
+}
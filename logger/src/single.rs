@@ -31,55 +31,114 @@ impl Log for Logger {
             return;
         }
 
-        // Tries to re-use a buffer from the pool or allocate a new buffer to
-        // to avoid blocking and try to avoid dropping the message. Message may
-        // still be dropped if the log_filled queue is full.
-        let mut buffer = self
-            .log_cleared
-            .pop()
-            .unwrap_or_else(|| Vec::with_capacity(self.buffer_size));
+        let mut buffer = get_buffer(&self.log_cleared, self.buffer_size);
 
         // Write the log message into the buffer and send to the receiver
         if (self.format)(&mut buffer, DateTime::recent(), record).is_ok() {
-            let bytes = buffer.len();
-
-            // Note this may drop a log message, but avoids blocking. The
-            // preference here is to preserve log messages which lead up to the
-            // point where we begin to drop log messages. For example, if an
-            // error begins to happen which causes very many log messages, it is
-            // more beneficial to have the history leading up to the issue than
-            // to preserve more recent error messages.
-            if self.log_filled.push(buffer).is_ok() {
-                LOG_WRITE.increment();
-                LOG_WRITE_BYTE.add(bytes as _);
-            } else {
-                LOG_DROP.increment();
-                LOG_DROP_BYTE.add(bytes as _);
-            }
+            send_buffer(&self.log_filled, buffer);
         }
     }
 
     fn flush(&self) {}
 }
 
+// Tries to re-use a buffer from the pool or allocate a new buffer to avoid
+// blocking and try to avoid dropping the message.
+fn get_buffer(log_cleared: &Queue<LogBuffer>, buffer_size: usize) -> LogBuffer {
+    log_cleared
+        .pop()
+        .unwrap_or_else(|| Vec::with_capacity(buffer_size))
+}
+
+// Sends a filled buffer to the drain. Returns `false` if the message was
+// dropped because the log_filled queue is full.
+fn send_buffer(log_filled: &Queue<LogBuffer>, buffer: LogBuffer) -> bool {
+    let bytes = buffer.len();
+
+    // Note this may drop a log message, but avoids blocking. The preference
+    // here is to preserve log messages which lead up to the point where we
+    // begin to drop log messages. For example, if an error begins to happen
+    // which causes very many log messages, it is more beneficial to have the
+    // history leading up to the issue than to preserve more recent error
+    // messages.
+    //
+    // The depth is incremented before the push, as the drain may pop and
+    // decrement it as soon as the push completes.
+    LOG_QUEUE_DEPTH.increment();
+    if log_filled.push(buffer).is_ok() {
+        true
+    } else {
+        LOG_QUEUE_DEPTH.decrement();
+        LOG_DROP.increment();
+        LOG_DROP_BYTE.add(bytes as _);
+        false
+    }
+}
+
+/// A `BinaryLog` writes raw byte records to a single `Output` through the same
+/// asynchronous queue and `Drain` as a text log, but without any formatting.
+///
+/// This is useful for logs which are not UTF-8 text, for example, audit logs
+/// which are made up of length-prefixed binary records. As `log::Record`s can
+/// only carry formatted text, records are written through `BinaryLog::log`
+/// instead of the `log` macros. Records are written to the `Output` exactly as
+/// provided, so any framing must be part of the record.
+pub struct BinaryLog {
+    logger: Logger,
+}
+
+impl BinaryLog {
+    /// Send a raw record to the `Output`. Returns `false` if the record was
+    /// dropped because the queue is full.
+    pub fn log(&self, record: &[u8]) -> bool {
+        let mut buffer = get_buffer(&self.logger.log_cleared, self.logger.buffer_size);
+        buffer.extend_from_slice(record);
+        send_buffer(&self.logger.log_filled, buffer)
+    }
+}
+
 /// Implements a basic drain type which receives log messages over a queue and
 /// flushes them to a single buffered output.
+///
+/// Messages are coalesced into a write buffer so that many small messages
+/// result in a single write to the output, either once the write buffer
+/// reaches its configured size or at the end of each flush.
 pub(crate) struct LogDrain {
     log_filled: Queue<LogBuffer>,
     log_cleared: Queue<LogBuffer>,
     buffer_size: usize,
+    write_buffer: Vec<u8>,
+    write_buffer_size: usize,
     output: Box<dyn Output>,
 }
 
+impl LogDrain {
+    // Writes the coalesced messages to the output. On failure, the pending
+    // messages are dropped.
+    fn write(&mut self) -> Result<(), Error> {
+        if self.write_buffer.is_empty() {
+            return Ok(());
+        }
+        let bytes = self.write_buffer.len();
+        let result = self.output.write_all(&self.write_buffer);
+        self.write_buffer.clear();
+        if let Err(e) = result {
+            LOG_WRITE_EX.increment();
+            warn!("failed write to log buffer: {}", e);
+            return Err(e);
+        }
+        LOG_WRITE.increment();
+        LOG_WRITE_BYTE.add(bytes as _);
+        Ok(())
+    }
+}
+
 impl Drain for LogDrain {
     fn flush(&mut self) -> Result<(), Error> {
         LOG_FLUSH.increment();
         while let Some(mut log_buffer) = self.log_filled.pop() {
-            if let Err(e) = self.output.write_all(&log_buffer) {
-                LOG_WRITE_EX.increment();
-                warn!("failed write to log buffer: {}", e);
-                return Err(e);
-            }
+            LOG_QUEUE_DEPTH.decrement();
+            self.write_buffer.extend_from_slice(&log_buffer);
 
             // shrink oversized buffer
             if log_buffer.len() > self.buffer_size {
@@ -91,7 +150,12 @@ impl Drain for LogDrain {
             // recycle the buffer, buffer will be dropped if the pool is full
             log_buffer.clear();
             let _ = self.log_cleared.push(log_buffer);
+
+            if self.write_buffer.len() >= self.write_buffer_size {
+                self.write()?;
+            }
         }
+        self.write()?;
 
         if let Err(e) = self.output.flush() {
             LOG_FLUSH_EX.increment();
@@ -108,6 +172,7 @@ impl Drain for LogDrain {
 pub struct LogBuilder {
     log_queue_depth: usize,
     single_message_size: usize,
+    write_buffer_size: usize,
     format: FormatFunction,
     level_filter: LevelFilter,
     output: Option<Box<dyn Output>>,
@@ -118,6 +183,7 @@ impl Default for LogBuilder {
         Self {
             log_queue_depth: 4096,
             single_message_size: 1024,
+            write_buffer_size: 64 * 1024,
             format: default_format,
             level_filter: LevelFilter::Trace,
             output: None,
@@ -146,6 +212,15 @@ impl LogBuilder {
         self
     }
 
+    /// Sets the size of the buffer used to coalesce messages when they are
+    /// flushed to the output. Messages are written to the output once this
+    /// many bytes are pending, and at the end of each flush. Larger buffers
+    /// result in fewer writes to the output.
+    pub fn write_buffer_size(mut self, bytes: usize) -> Self {
+        self.write_buffer_size = bytes;
+        self
+    }
+
     /// Sets the output for the logger.
     pub fn output(mut self, output: Box<dyn Output>) -> Self {
         self.output = Some(output);
@@ -179,6 +254,8 @@ impl LogBuilder {
                 log_filled,
                 log_cleared,
                 buffer_size: self.single_message_size,
+                write_buffer: Vec::with_capacity(self.write_buffer_size),
+                write_buffer_size: self.write_buffer_size,
                 output,
             };
             Ok((logger, log_handle))
@@ -188,6 +265,14 @@ impl LogBuilder {
         }
     }
 
+    /// Consumes the builder and returns a `BinaryLog` along with the `Drain`
+    /// which must be periodically flushed to write the records to the output.
+    /// The format function and level filter are not used by a `BinaryLog`.
+    pub fn build_binary(self) -> Result<(BinaryLog, Box<dyn Drain>), &'static str> {
+        let (logger, drain) = self.build_raw()?;
+        Ok((BinaryLog { logger }, Box::new(drain)))
+    }
+
     /// Consumes the builder and returns an `AsyncLog`.
     pub fn build(self) -> Result<AsyncLog, &'static str> {
         let (logger, drain) = self.build_raw()?;
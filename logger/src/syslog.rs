@@ -0,0 +1,389 @@
+// Copyright 2021 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use crate::*;
+
+use rustcommon_time::{DateTime, SecondsFormat};
+use std::io::{Error, ErrorKind, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+#[cfg(unix)]
+use std::path::Path;
+
+/// The default path of the local syslog socket.
+pub const SYSLOG_SOCKET: &str = "/dev/log";
+
+// Severity used for lines which were not formatted with `syslog_format`.
+const DEFAULT_SEVERITY: u8 = 6;
+
+/// Syslog facilities, as defined in RFC5424 section 6.2.1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Facility {
+    Kernel = 0,
+    User = 1,
+    Mail = 2,
+    Daemon = 3,
+    Auth = 4,
+    Syslog = 5,
+    Lpr = 6,
+    News = 7,
+    Uucp = 8,
+    Cron = 9,
+    AuthPriv = 10,
+    Ftp = 11,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+/// Returns the syslog severity for a log `Level`.
+///
+/// `Trace` has no syslog equivalent and shares the `debug` severity.
+pub fn syslog_severity(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+/// A format function to be used with `SyslogOutput`. Each line is prefixed
+/// with the syslog severity of the record and its timestamp, which the output
+/// uses to build the RFC5424 header for the message.
+pub fn syslog_format(
+    w: &mut dyn std::io::Write,
+    now: DateTime,
+    record: &Record,
+) -> Result<(), std::io::Error> {
+    write!(w, "<{}>", syslog_severity(record.level()))?;
+    now.write_rfc3339_opts(w, SecondsFormat::Micros, true)?;
+    writeln!(
+        w,
+        " [{}] {}",
+        record.module_path().unwrap_or("<unnamed>"),
+        record.args()
+    )
+}
+
+enum Transport {
+    #[cfg(unix)]
+    Unix(UnixDatagram),
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+}
+
+/// An output which sends each log line as a syslog message formatted per
+/// RFC5424, either to the local syslog socket or to a remote UDP or TCP
+/// endpoint.
+///
+/// Lines should be formatted with `syslog_format` so that the severity and
+/// timestamp of each record are preserved. Lines in any other format are sent
+/// with `info` severity and the time at which they were written to the
+/// output. Messages sent over TCP are framed using octet counting, as
+/// described in RFC6587.
+pub struct SyslogOutput {
+    transport: Transport,
+    facility: Facility,
+    hostname: String,
+    app_name: String,
+    pending: Vec<u8>,
+}
+
+impl SyslogOutput {
+    /// Create an output which writes to the local syslog socket at `path`,
+    /// typically `SYSLOG_SOCKET`.
+    #[cfg(unix)]
+    pub fn unix<T: AsRef<Path>>(path: T) -> Result<Self, Error> {
+        Self::open(|| {
+            let socket = UnixDatagram::unbound()?;
+            socket.connect(path)?;
+            Ok(Transport::Unix(socket))
+        })
+    }
+
+    /// Create an output which sends messages to a syslog server over UDP. If
+    /// the address resolves to several addresses, the first is used.
+    pub fn udp<A: ToSocketAddrs>(addr: A) -> Result<Self, Error> {
+        Self::open(|| {
+            let addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
+                Error::new(ErrorKind::InvalidInput, "no address for syslog server")
+            })?;
+            // the local address must be of the same family as the server
+            let local: SocketAddr = if addr.is_ipv4() {
+                (Ipv4Addr::UNSPECIFIED, 0).into()
+            } else {
+                (Ipv6Addr::UNSPECIFIED, 0).into()
+            };
+            let socket = UdpSocket::bind(local)?;
+            socket.connect(addr)?;
+            Ok(Transport::Udp(socket))
+        })
+    }
+
+    /// Create an output which sends messages to a syslog server over TCP.
+    pub fn tcp<A: ToSocketAddrs>(addr: A) -> Result<Self, Error> {
+        Self::open(|| Ok(Transport::Tcp(TcpStream::connect(addr)?)))
+    }
+
+    fn open<F: FnOnce() -> Result<Transport, Error>>(connect: F) -> Result<Self, Error> {
+        LOG_OPEN.increment();
+        let transport = match connect() {
+            Ok(t) => t,
+            Err(e) => {
+                LOG_OPEN_EX.increment();
+                return Err(e);
+            }
+        };
+        Ok(Self {
+            transport,
+            facility: Facility::User,
+            hostname: default_hostname(),
+            app_name: default_app_name(),
+            pending: Vec::new(),
+        })
+    }
+
+    /// Sets the facility for all messages. Defaults to `Facility::User`.
+    pub fn facility(mut self, facility: Facility) -> Self {
+        self.facility = facility;
+        self
+    }
+
+    /// Sets the hostname sent with each message. Defaults to the hostname of
+    /// the local machine, if it can be determined.
+    pub fn hostname(mut self, hostname: &str) -> Self {
+        self.hostname = header_field(hostname, 255);
+        self
+    }
+
+    /// Sets the application name sent with each message. Defaults to the name
+    /// of the running executable.
+    pub fn app_name(mut self, app_name: &str) -> Self {
+        self.app_name = header_field(app_name, 48);
+        self
+    }
+
+    // Builds the syslog message for a single line.
+    fn message(&self, line: &[u8]) -> Vec<u8> {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let (severity, timestamp, msg) = match parse_line(line) {
+            Some(parsed) => parsed,
+            None => (
+                DEFAULT_SEVERITY,
+                DateTime::recent().to_rfc3339_opts(SecondsFormat::Micros, true),
+                line,
+            ),
+        };
+        let mut message = format!(
+            "<{}>1 {} {} {} {} - - ",
+            (self.facility as u8) * 8 + severity,
+            timestamp,
+            self.hostname,
+            self.app_name,
+            std::process::id(),
+        )
+        .into_bytes();
+        message.extend_from_slice(msg);
+        message
+    }
+
+    // Builds the syslog message for a single line and sends it.
+    fn send(&mut self, line: &[u8]) -> Result<(), Error> {
+        let message = self.message(line);
+        match &mut self.transport {
+            #[cfg(unix)]
+            Transport::Unix(socket) => socket.send(&message).map(|_| ()),
+            Transport::Udp(socket) => socket.send(&message).map(|_| ()),
+            Transport::Tcp(stream) => {
+                write!(stream, "{} ", message.len())?;
+                stream.write_all(&message)
+            }
+        }
+    }
+}
+
+impl Write for SyslogOutput {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.pending.extend_from_slice(buf);
+        while let Some(position) = self.pending.iter().position(|b| *b == b'\n') {
+            let rest = self.pending.split_off(position + 1);
+            let line = std::mem::replace(&mut self.pending, rest);
+            self.send(&line)?;
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::result::Result<(), Error> {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            self.send(&line)?;
+        }
+        match &mut self.transport {
+            Transport::Tcp(stream) => stream.flush(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Output for SyslogOutput {}
+
+// Splits a line produced by `syslog_format` into its severity, timestamp, and
+// message.
+fn parse_line(line: &[u8]) -> Option<(u8, String, &[u8])> {
+    let line = line.strip_prefix(b"<")?;
+    let end = line.iter().position(|b| *b == b'>')?;
+    let severity: u8 = std::str::from_utf8(&line[..end]).ok()?.parse().ok()?;
+    if severity > 7 {
+        return None;
+    }
+    let line = &line[(end + 1)..];
+    let end = line.iter().position(|b| *b == b' ')?;
+    let timestamp = std::str::from_utf8(&line[..end]).ok()?.to_string();
+    Some((severity, timestamp, &line[(end + 1)..]))
+}
+
+// Header fields must be non-empty printable ASCII without spaces, and are
+// limited in length. The NILVALUE `-` is used if nothing valid remains.
+fn header_field(value: &str, max_len: usize) -> String {
+    let value: String = value
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(max_len)
+        .collect();
+    if value.is_empty() {
+        "-".to_string()
+    } else {
+        value
+    }
+}
+
+fn default_hostname() -> String {
+    let hostname = std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .unwrap_or_default();
+    header_field(hostname.trim(), 255)
+}
+
+fn default_app_name() -> String {
+    let name = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_default();
+    header_field(&name, 48)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(level: Level, message: &str) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        syslog_format(
+            &mut buffer,
+            DateTime::recent(),
+            &Record::builder()
+                .args(format_args!("{}", message))
+                .level(level)
+                .module_path(Some("test"))
+                .build(),
+        )
+        .unwrap();
+        buffer
+    }
+
+    #[test]
+    fn severity() {
+        assert_eq!(syslog_severity(Level::Error), 3);
+        assert_eq!(syslog_severity(Level::Warn), 4);
+        assert_eq!(syslog_severity(Level::Info), 6);
+        assert_eq!(syslog_severity(Level::Debug), 7);
+        assert_eq!(syslog_severity(Level::Trace), 7);
+    }
+
+    #[test]
+    fn parse() {
+        let line = format(Level::Warn, "hello world");
+        let (severity, timestamp, msg) = parse_line(&line).unwrap();
+        assert_eq!(severity, 4);
+        assert!(timestamp.ends_with('Z'));
+        assert_eq!(msg, b"[test] hello world\n");
+
+        assert!(parse_line(b"hello world").is_none());
+        assert!(parse_line(b"<8>2022-01-01T00:00:00Z hello").is_none());
+        assert!(parse_line(b"<x>2022-01-01T00:00:00Z hello").is_none());
+        assert!(parse_line(b"<3>no-space").is_none());
+    }
+
+    #[test]
+    fn fields() {
+        assert_eq!(header_field("my host", 255), "myhost");
+        assert_eq!(header_field("", 255), "-");
+        assert_eq!(header_field(" \u{e9}", 255), "-");
+        assert_eq!(header_field("application", 3), "app");
+    }
+
+    #[test]
+    fn header() {
+        let server = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        let mut output = SyslogOutput::udp(server.local_addr().unwrap())
+            .unwrap()
+            .facility(Facility::Local0)
+            .hostname("host")
+            .app_name("app");
+
+        for (level, pri) in [
+            (Level::Error, 131),
+            (Level::Warn, 132),
+            (Level::Info, 134),
+            (Level::Debug, 135),
+            (Level::Trace, 135),
+        ] {
+            let line = format(level, "hello");
+            let (_, timestamp, _) = parse_line(&line).unwrap();
+            output.write_all(&line).unwrap();
+
+            let mut buffer = [0; 1024];
+            let len = server.recv(&mut buffer).unwrap();
+            let message = std::str::from_utf8(&buffer[..len]).unwrap();
+            let fields: Vec<&str> = message.splitn(8, ' ').collect();
+            assert_eq!(fields[0], format!("<{}>1", pri));
+            assert_eq!(fields[1], timestamp);
+            assert_eq!(fields[2], "host");
+            assert_eq!(fields[3], "app");
+            assert_eq!(fields[4], std::process::id().to_string());
+            assert_eq!(fields[5], "-");
+            assert_eq!(fields[6], "-");
+            assert_eq!(fields[7], "[test] hello");
+        }
+
+        // lines in other formats are sent with the default severity
+        output.write_all(b"plain\n").unwrap();
+        let mut buffer = [0; 1024];
+        let len = server.recv(&mut buffer).unwrap();
+        let message = std::str::from_utf8(&buffer[..len]).unwrap();
+        assert!(message.starts_with("<134>1 "));
+        assert!(message.ends_with(" - - plain"));
+    }
+
+    #[test]
+    fn udp_ipv6() {
+        // skip when the host has no IPv6 loopback
+        let server = match UdpSocket::bind(("::1", 0)) {
+            Ok(server) => server,
+            Err(_) => return,
+        };
+        let mut output = SyslogOutput::udp(server.local_addr().unwrap()).unwrap();
+        output.write_all(&format(Level::Info, "hello")).unwrap();
+        let mut buffer = [0; 1024];
+        let len = server.recv(&mut buffer).unwrap();
+        assert!(buffer[..len].ends_with(b"[test] hello"));
+    }
+}
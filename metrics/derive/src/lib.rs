@@ -25,6 +25,12 @@ mod metric;
 ///   their Cargo.toml.
 /// - (optional) `description`: A textual description of the metric. If not
 ///   specified, or specified as a blank string then defaults to None
+/// - (optional) `hidden`: Whether the metric should be hidden from default
+///   exports, for example, because it is deprecated but must be kept for
+///   compatibility. Defaults to `false`. See `Metrics::visible`.
+/// - (optional) `meta`: Freeform static metadata as a list of key-value pairs,
+///   for example, `meta(aggregation = "sum", team = "search")`. Exporters may
+///   use this for backend-specific hints. See `MetricEntry::metadata`.
 ///
 /// [`Deref`]: std::ops::Deref
 /// [`DerefMut`]: std::ops::DerefMut
@@ -7,8 +7,9 @@ use proc_macro2::{Span, TokenStream};
 use proc_macro_crate::FoundCrate;
 use quote::{quote, ToTokens};
 use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
-use syn::{parse_quote, Error, Expr, Ident, ItemStatic, Path, Token};
+use syn::{parenthesized, parse_quote, token, Error, Expr, Ident, ItemStatic, Path, Token};
 
 struct SingleArg<T> {
     ident: ArgName,
@@ -34,11 +35,63 @@ impl<T: ToTokens> ToTokens for SingleArg<T> {
     }
 }
 
+struct MetaPair {
+    key: Ident,
+    eq: Token![=],
+    value: Expr,
+}
+
+impl Parse for MetaPair {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            key: input.parse()?,
+            eq: input.parse()?,
+            value: input.parse()?,
+        })
+    }
+}
+
+impl ToTokens for MetaPair {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.key.to_tokens(tokens);
+        self.eq.to_tokens(tokens);
+        self.value.to_tokens(tokens);
+    }
+}
+
+/// The `meta(key = "value", ...)` argument.
+struct MetaArg {
+    ident: ArgName,
+    paren: token::Paren,
+    pairs: Punctuated<MetaPair, Token![,]>,
+}
+
+impl Parse for MetaArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        Ok(Self {
+            ident: input.parse()?,
+            paren: parenthesized!(content in input),
+            pairs: content.parse_terminated(MetaPair::parse)?,
+        })
+    }
+}
+
+impl ToTokens for MetaArg {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.ident.to_tokens(tokens);
+        self.paren
+            .surround(tokens, |tokens| self.pairs.to_tokens(tokens));
+    }
+}
+
 #[derive(Default)]
 struct MetricArgs {
     name: Option<SingleArg<Expr>>,
     namespace: Option<SingleArg<Expr>>,
     description: Option<SingleArg<Expr>>,
+    hidden: Option<SingleArg<Expr>>,
+    meta: Option<MetaArg>,
     krate: Option<SingleArg<Path>>,
 }
 
@@ -86,6 +139,29 @@ impl Parse for MetricArgs {
                         Some(_) => return duplicate_arg_error(description.span(), &arg),
                     }
                 }
+                "hidden" => {
+                    let hidden = input.parse()?;
+                    match args.hidden {
+                        None => args.hidden = Some(hidden),
+                        Some(_) => return duplicate_arg_error(hidden.span(), &arg),
+                    }
+                }
+                "meta" => {
+                    let meta: MetaArg = input.parse()?;
+                    let mut keys = std::collections::HashSet::new();
+                    for pair in meta.pairs.iter() {
+                        if !keys.insert(pair.key.to_string()) {
+                            return Err(Error::new(
+                                pair.key.span(),
+                                format!("Unexpected duplicate metadata key '{}'", pair.key),
+                            ));
+                        }
+                    }
+                    match args.meta {
+                        None => args.meta = Some(meta),
+                        Some(_) => return duplicate_arg_error(meta.span(), &arg),
+                    }
+                }
                 "crate" => {
                     let krate = SingleArg {
                         ident: input.parse()?,
@@ -152,6 +228,27 @@ pub(crate) fn metric(
         }
     };
 
+    let hidden: TokenStream = match args.hidden {
+        Some(hidden) => hidden.value.to_token_stream(),
+        None => {
+            quote! {false}
+        }
+    };
+
+    let metadata: TokenStream = match args.meta {
+        Some(meta) => {
+            let pairs = meta.pairs.iter().map(|pair| {
+                let key = pair.key.to_string();
+                let value = &pair.value;
+                quote! { (#key, #value) }
+            });
+            quote! { &[#(#pairs),*] }
+        }
+        None => {
+            quote! {&[]}
+        }
+    };
+
     let static_name = &item.ident;
     let static_expr = &item.expr;
     let static_type = &item.ty;
@@ -169,7 +266,9 @@ pub(crate) fn metric(
             #krate::MetricWrapper(&#static_name.metric),
             #static_name.name(),
             #namespace,
-            #description
+            #description,
+            #hidden,
+            #metadata
         );
 
         #krate::MetricInstance::new(#static_expr, #name, #description)
@@ -2,7 +2,11 @@
 // Licensed under the Apache License, Version 2.0
 // http://www.apache.org/licenses/LICENSE-2.0
 
+#[cfg(feature = "last-updated")]
+use crate::updated::LastUpdated;
 use crate::Metric;
+#[cfg(feature = "last-updated")]
+use rustcommon_time::{Nanoseconds, UnixInstant};
 use std::any::Any;
 use std::sync::atomic::{AtomicU64, Ordering};
 
@@ -12,6 +16,11 @@ use std::sync::atomic::{AtomicU64, Ordering};
 /// uses an unsigned 64-bit integer so for most use cases this should be
 /// unlikely.
 ///
+/// All operations use `Ordering::Relaxed` by default, which is sufficient
+/// when the counter is read independently of other memory. The `_ordered`
+/// variants of each operation accept an explicit `Ordering` for cases where
+/// the counter must be ordered with respect to other operations.
+///
 /// # Example
 /// ```
 /// # use rustcommon_metrics::{metric, Counter};
@@ -25,7 +34,11 @@ use std::sync::atomic::{AtomicU64, Ordering};
 /// # a_method();
 /// ```
 #[derive(Default, Debug)]
-pub struct Counter(AtomicU64);
+pub struct Counter {
+    value: AtomicU64,
+    #[cfg(feature = "last-updated")]
+    updated: LastUpdated,
+}
 
 impl Counter {
     /// Create a counter initialized to 0.
@@ -35,7 +48,11 @@ impl Counter {
 
     /// Create a counter initialized to `value`.
     pub const fn with_value(value: u64) -> Self {
-        Self(AtomicU64::new(value))
+        Self {
+            value: AtomicU64::new(value),
+            #[cfg(feature = "last-updated")]
+            updated: LastUpdated::new(),
+        }
     }
 
     #[inline]
@@ -45,23 +62,62 @@ impl Counter {
 
     #[inline]
     pub fn add(&self, value: u64) -> u64 {
-        self.0.fetch_add(value, Ordering::Relaxed)
+        self.add_ordered(value, Ordering::Relaxed)
     }
 
     #[inline]
     pub fn value(&self) -> u64 {
-        self.0.load(Ordering::Relaxed)
+        self.value_ordered(Ordering::Relaxed)
     }
 
     #[inline]
     pub fn set(&self, value: u64) -> u64 {
-        self.0.swap(value, Ordering::Relaxed)
+        self.set_ordered(value, Ordering::Relaxed)
+    }
+
+    /// Increment the counter by 1 using the provided `Ordering`.
+    #[inline]
+    pub fn increment_ordered(&self, ordering: Ordering) -> u64 {
+        self.add_ordered(1, ordering)
+    }
+
+    /// Add `value` to the counter using the provided `Ordering`.
+    #[inline]
+    pub fn add_ordered(&self, value: u64, ordering: Ordering) -> u64 {
+        #[cfg(feature = "last-updated")]
+        self.updated.touch();
+        self.value.fetch_add(value, ordering)
+    }
+
+    /// Read the value of the counter using the provided `Ordering`, which
+    /// must not be `Release` or `AcqRel`.
+    #[inline]
+    pub fn value_ordered(&self, ordering: Ordering) -> u64 {
+        self.value.load(ordering)
+    }
+
+    /// Set the value of the counter using the provided `Ordering`.
+    #[inline]
+    pub fn set_ordered(&self, value: u64, ordering: Ordering) -> u64 {
+        #[cfg(feature = "last-updated")]
+        self.updated.touch();
+        self.value.swap(value, ordering)
     }
 
     #[inline]
     pub fn reset(&self) -> u64 {
         self.set(0)
     }
+
+    /// Returns the time this counter was last written, or `None` if it has
+    /// not been written since it was created.
+    ///
+    /// The time is read from the cached clock, so it is only as precise as
+    /// the most recent call to `rustcommon_time::refresh_clock()`.
+    #[cfg(feature = "last-updated")]
+    pub fn last_updated(&self) -> Option<UnixInstant<Nanoseconds<u64>>> {
+        self.updated.get()
+    }
 }
 
 impl Metric for Counter {
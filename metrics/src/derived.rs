@@ -0,0 +1,68 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use crate::Metric;
+use std::any::Any;
+
+/// A metric whose value is computed from other metrics when it is read.
+///
+/// A derived metric stores no state of its own. Instead, the provided function
+/// is evaluated each time the value is read, so the value is always consistent
+/// with its inputs at the time of the read.
+///
+/// Statically registered derived metrics use a function pointer. Derived
+/// metrics which need to capture state, such as those registered dynamically,
+/// can instead use a boxed closure.
+///
+/// # Example
+/// ```
+/// # use rustcommon_metrics::{metric, Counter, DerivedMetric};
+/// #[metric(name = "cache/hit")]
+/// static HIT: Counter = Counter::new();
+///
+/// #[metric(name = "cache/miss")]
+/// static MISS: Counter = Counter::new();
+///
+/// #[metric(name = "cache/hit_ratio")]
+/// static HIT_RATIO: DerivedMetric = DerivedMetric::new(|| {
+///     let hit = HIT.value() as f64;
+///     let total = hit + MISS.value() as f64;
+///     if total == 0.0 { 0.0 } else { hit / total }
+/// });
+///
+/// HIT.add(3);
+/// MISS.increment();
+/// assert_eq!(HIT_RATIO.value(), 0.75);
+/// ```
+pub struct DerivedMetric<F = fn() -> f64> {
+    f: F,
+}
+
+impl<F> DerivedMetric<F> {
+    /// Create a new derived metric which is computed by the provided
+    /// function.
+    pub const fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+impl<F> DerivedMetric<F>
+where
+    F: Fn() -> f64,
+{
+    /// Compute the current value of this metric.
+    #[inline]
+    pub fn value(&self) -> f64 {
+        (self.f)()
+    }
+}
+
+impl<F> Metric for DerivedMetric<F>
+where
+    F: Fn() -> f64 + Send + Sync + 'static,
+{
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+}
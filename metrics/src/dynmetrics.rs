@@ -6,7 +6,8 @@
 //! metrics.
 //!
 //! Generally users should not need to use anything in this module with the
-//! exception of [`DynPinnedMetric`] and [`DynBoxedMetric`].
+//! exception of [`DynPinnedMetric`], [`DynBoxedMetric`], [`ScopedMetric`], and
+//! [`register_metric`].
 
 use std::borrow::Cow;
 use std::marker::PhantomPinned;
@@ -16,21 +17,31 @@ use std::pin::Pin;
 use crate::{Metric, MetricEntry};
 
 // We use parking_lot here since it avoids lock poisioning
-use parking_lot::{const_rwlock, RwLock, RwLockReadGuard};
+use parking_lot::{const_mutex, const_rwlock, Mutex, RwLock, RwLockReadGuard};
 
 pub(crate) struct DynMetricsRegistry {
     metrics: Vec<MetricEntry>,
+    // the number of metrics which have ever been registered, used to assign
+    // each a unique id
+    registered: u64,
 }
 
 impl DynMetricsRegistry {
     const fn new() -> Self {
         Self {
             metrics: Vec::new(),
+            registered: 0,
         }
     }
 
-    fn register(&mut self, entry: MetricEntry) {
+    fn register(&mut self, mut entry: MetricEntry) -> u64 {
+        // dynamic ids follow the ids of the static metrics and are never
+        // reused, so they remain in increasing order within the registry
+        let id = crate::export::METRICS.len() as u64 + self.registered;
+        entry.id = Some(id);
+        self.registered += 1;
         self.metrics.push(entry);
+        id
     }
 
     fn unregister(&mut self, metric: *const dyn Metric) {
@@ -38,13 +49,36 @@ impl DynMetricsRegistry {
             .retain(|x| x.metric.0 as *const () != metric as *const ());
     }
 
+    fn unregister_id(&mut self, id: u64) {
+        self.metrics.retain(|x| x.id != Some(id));
+    }
+
     pub(crate) fn metrics(&self) -> &[MetricEntry] {
         &self.metrics
     }
+
+    pub(crate) fn get_by_id(&self, id: u64) -> Option<&MetricEntry> {
+        self.metrics
+            .binary_search_by_key(&Some(id), |entry| entry.id)
+            .ok()
+            .map(|index| &self.metrics[index])
+    }
 }
 
 static REGISTRY: RwLock<DynMetricsRegistry> = const_rwlock(DynMetricsRegistry::new());
 
+// Metrics which could not be unregistered when a `ScopedMetric` was dropped
+// because the registry was locked. They are kept alive, and so remain valid
+// for the registry to reference, until the next registration or
+// unregistration.
+static DEFERRED: Mutex<Vec<Box<dyn Send>>> = const_mutex(Vec::new());
+
+fn drop_deferred() {
+    // take the metrics out first, as dropping them unregisters them
+    let deferred = std::mem::take(&mut *DEFERRED.lock());
+    drop(deferred);
+}
+
 pub(crate) fn get_registry() -> RwLockReadGuard<'static, DynMetricsRegistry> {
     REGISTRY.read()
 }
@@ -57,6 +91,7 @@ pub(crate) fn get_registry() -> RwLockReadGuard<'static, DynMetricsRegistry> {
 /// safety guarantees.
 pub fn register(entry: MetricEntry) {
     REGISTRY.write().register(entry);
+    drop_deferred();
 }
 
 /// Unregisters all dynamic entries added via [`register`] that point to the
@@ -66,6 +101,74 @@ pub fn register(entry: MetricEntry) {
 /// registered multiple times.
 pub fn unregister(metric: *const dyn Metric) {
     REGISTRY.write().unregister(metric);
+    drop_deferred();
+}
+
+/// Registers a new dynamic metric entry and returns a [`RegisteredMetric`]
+/// handle which unregisters it when dropped.
+///
+/// This is the function-based counterpart to the [`metric`] attribute, for
+/// metrics which are only known at runtime. Unlike [`unregister`], dropping
+/// the handle only removes this entry, so the same metric may be registered
+/// under several names, each with its own handle. As with [`register`], take
+/// care to note how this interacts with [`MetricEntry`]'s safety guarantees.
+///
+/// Dropping the handle waits for the registry, so it must not be dropped while
+/// a [`Metrics`] instance is alive on the same thread.
+///
+/// # Example
+/// ```
+/// # use rustcommon_metrics::*;
+/// static CONNECTIONS: Counter = Counter::new();
+///
+/// let entry = MetricEntry::new(&CONNECTIONS, "connections".into())
+///     .with_description("connections accepted by the listener");
+/// let handle = register_metric(entry);
+/// assert_eq!(
+///     metrics().get_by_id(handle.id()).map(|entry| entry.name().to_string()),
+///     Some("connections".to_string())
+/// );
+///
+/// drop(handle);
+/// assert!(metrics().dynamic_metrics().is_empty());
+/// ```
+///
+/// [`metric`]: crate::metric
+/// [`Metrics`]: crate::Metrics
+pub fn register_metric(entry: MetricEntry) -> RegisteredMetric {
+    let id = REGISTRY.write().register(entry);
+    drop_deferred();
+    RegisteredMetric { id }
+}
+
+/// A handle to a metric entry registered with [`register_metric`]. The entry
+/// is unregistered when the handle is dropped.
+#[must_use = "the metric is unregistered when the handle is dropped"]
+pub struct RegisteredMetric {
+    id: u64,
+}
+
+impl RegisteredMetric {
+    /// Returns the id of the registered entry, which can be used to look it up
+    /// with [`Metrics::get_by_id`].
+    ///
+    /// [`Metrics::get_by_id`]: crate::Metrics::get_by_id
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Consume the handle, leaving the entry registered for the remainder of
+    /// the program.
+    pub fn leak(self) {
+        std::mem::forget(self)
+    }
+}
+
+impl Drop for RegisteredMetric {
+    fn drop(&mut self) {
+        REGISTRY.write().unregister_id(self.id);
+        drop_deferred();
+    }
 }
 
 /// A dynamic metric that stores the metric inline.
@@ -119,6 +222,10 @@ impl<M: Metric> DynPinnedMetric<M> {
     /// Calling this multiple times will result in the same metric being
     /// registered multiple times under potentially different names.
     pub fn register(self: Pin<&Self>, name: impl Into<Cow<'static, str>>) {
+        DynMetricBuilder::new(name).register(self)
+    }
+
+    fn register_entry(self: Pin<&Self>, builder: DynMetricBuilder) {
         // SAFETY:
         // To prove that this is safe we need to list out a few guarantees/requirements:
         //  - Pin ensures that the memory of this struct instance will not be reused
@@ -134,7 +241,14 @@ impl<M: Metric> DynPinnedMetric<M> {
         // point, drop calls unregister which will drop the MetricEntry instance. This
         // ensures that the references to self.metric in REGISTRY will always be valid
         // and that this method is safe.
-        unsafe { register(MetricEntry::new_unchecked(&self.metric, name.into())) };
+        let mut entry = unsafe { MetricEntry::new_unchecked(&self.metric, builder.name) };
+        if let Some(namespace) = builder.namespace {
+            entry = entry.with_namespace(namespace);
+        }
+        if let Some(description) = builder.description {
+            entry = entry.with_description(description);
+        }
+        register(entry);
     }
 }
 
@@ -198,6 +312,70 @@ impl<M: Metric> DynBoxedMetric<M> {
     }
 }
 
+/// A `DynMetricBuilder` allows for registering a dynamic metric with the same
+/// metadata that is available for metrics declared with the [`metric`]
+/// attribute.
+///
+/// # Example
+/// ```
+/// # use rustcommon_metrics::*;
+/// let my_counter = DynMetricBuilder::new("requests")
+///     .namespace("server")
+///     .description("the number of requests received")
+///     .build(Counter::new());
+///
+/// let metrics = metrics();
+/// let entry = &metrics.dynamic_metrics()[0];
+/// assert_eq!(entry.name(), "requests");
+/// assert_eq!(entry.namespace(), Some("server"));
+/// assert_eq!(entry.description(), Some("the number of requests received"));
+/// ```
+///
+/// [`metric`]: crate::metric
+pub struct DynMetricBuilder {
+    name: Cow<'static, str>,
+    namespace: Option<Cow<'static, str>>,
+    description: Option<Cow<'static, str>>,
+}
+
+impl DynMetricBuilder {
+    /// Create a new builder for a metric with the provided `name`.
+    pub fn new(name: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            name: name.into(),
+            namespace: None,
+            description: None,
+        }
+    }
+
+    /// Sets the namespace of the metric.
+    pub fn namespace(mut self, namespace: impl Into<Cow<'static, str>>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Sets the description of the metric.
+    pub fn description(mut self, description: impl Into<Cow<'static, str>>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Consume the builder and return a registered [`DynBoxedMetric`] which
+    /// holds the provided metric.
+    pub fn build<M: Metric>(self, metric: M) -> DynBoxedMetric<M> {
+        let metric = DynBoxedMetric::unregistered(metric);
+        self.register(metric.metric.as_ref());
+        metric
+    }
+
+    /// Consume the builder and register the provided [`DynPinnedMetric`]. As
+    /// with [`DynPinnedMetric::register`], the metric will be unregistered
+    /// when it is dropped.
+    pub fn register<M: Metric>(self, metric: Pin<&DynPinnedMetric<M>>) {
+        metric.register_entry(self)
+    }
+}
+
 impl<M: Metric> Deref for DynBoxedMetric<M> {
     type Target = M;
 
@@ -206,3 +384,84 @@ impl<M: Metric> Deref for DynBoxedMetric<M> {
         &*self.metric
     }
 }
+
+/// A dynamic metric which is registered for the duration of a scope.
+///
+/// The metric is registered when the `ScopedMetric` is created and is
+/// unregistered when it is dropped, including when unwinding from a panic. This
+/// is intended for the common pattern of a metric which exists for the
+/// duration of a task.
+///
+/// Unlike [`DynBoxedMetric`], dropping a `ScopedMetric` never blocks waiting
+/// for the registry. If the registry is locked, for example, because a
+/// [`Metrics`] instance is alive on the same thread, the metric is kept alive
+/// and remains visible until the next time a dynamic metric is registered or
+/// unregistered. This avoids the deadlock described in the [`Metrics`]
+/// documentation at the cost of the metric outliving its scope for a short
+/// time.
+///
+/// # Example
+/// ```
+/// # use rustcommon_metrics::*;
+/// fn handle_task() {
+///     let processed = ScopedMetric::new(Counter::new(), "task.processed");
+///     processed.increment();
+///
+///     // the metric is visible while the task runs, even to code which is
+///     // holding the registry when the task completes
+///     let metrics = metrics();
+///     assert_eq!(metrics.dynamic_metrics()[0].name(), "task.processed");
+/// }
+/// handle_task();
+/// ```
+///
+/// [`Metrics`]: crate::Metrics
+pub struct ScopedMetric<M: Metric> {
+    metric: Option<DynBoxedMetric<M>>,
+}
+
+impl<M: Metric> ScopedMetric<M> {
+    /// Create and register a new scoped metric with the provided `name`.
+    pub fn new(metric: M, name: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            metric: Some(DynBoxedMetric::new(metric, name)),
+        }
+    }
+}
+
+impl<M: Metric> From<DynBoxedMetric<M>> for ScopedMetric<M> {
+    fn from(metric: DynBoxedMetric<M>) -> Self {
+        Self {
+            metric: Some(metric),
+        }
+    }
+}
+
+impl<M: Metric> Drop for ScopedMetric<M> {
+    fn drop(&mut self) {
+        let metric = match self.metric.take() {
+            Some(metric) => metric,
+            None => return,
+        };
+
+        match REGISTRY.try_write() {
+            Some(mut registry) => {
+                registry.unregister(&*metric as &dyn Metric);
+                drop(registry);
+                drop(metric);
+                drop_deferred();
+            }
+            None => DEFERRED.lock().push(Box::new(metric)),
+        }
+    }
+}
+
+impl<M: Metric> Deref for ScopedMetric<M> {
+    type Target = M;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // the metric is only taken when it is dropped
+        self.metric.as_ref().unwrap()
+    }
+}
@@ -2,7 +2,11 @@
 // Licensed under the Apache License, Version 2.0
 // http://www.apache.org/licenses/LICENSE-2.0
 
+#[cfg(feature = "last-updated")]
+use crate::updated::LastUpdated;
 use crate::Metric;
+#[cfg(feature = "last-updated")]
+use rustcommon_time::{Nanoseconds, UnixInstant};
 use std::any::Any;
 use std::sync::atomic::{AtomicI64, Ordering};
 
@@ -12,6 +16,18 @@ use std::sync::atomic::{AtomicI64, Ordering};
 /// internally it uses a signed 64-bit integer so for most use cases this should
 /// be unlikely.
 ///
+/// All operations use `Ordering::Relaxed` by default, which is sufficient
+/// when the gauge is read independently of other memory. The `_ordered`
+/// variants of each operation accept an explicit `Ordering` for cases where
+/// the gauge must be ordered with respect to other operations.
+///
+/// A gauge may have a baseline, for example, when it represents memory usage
+/// above a fixed floor. The baseline does not change the value of the gauge,
+/// but [`adjusted_value`] reports the value relative to it, and exporters
+/// report the adjusted value. The baseline defaults to 0.
+///
+/// [`adjusted_value`]: Gauge::adjusted_value
+///
 /// # Example
 /// ```
 /// # use rustcommon_metrics::{metric, Gauge};
@@ -26,7 +42,12 @@ use std::sync::atomic::{AtomicI64, Ordering};
 /// # a_method();
 /// ```
 #[derive(Default, Debug)]
-pub struct Gauge(AtomicI64);
+pub struct Gauge {
+    value: AtomicI64,
+    baseline: AtomicI64,
+    #[cfg(feature = "last-updated")]
+    updated: LastUpdated,
+}
 
 impl Gauge {
     /// Create a new guage with the default value of 0.
@@ -36,7 +57,23 @@ impl Gauge {
 
     /// Create a new guage with the provided initial value.
     pub const fn with_value(value: i64) -> Self {
-        Self(AtomicI64::new(value))
+        Self {
+            value: AtomicI64::new(value),
+            baseline: AtomicI64::new(0),
+            #[cfg(feature = "last-updated")]
+            updated: LastUpdated::new(),
+        }
+    }
+
+    /// Create a new gauge with the default value of 0 and the provided
+    /// baseline. See [`adjusted_value`](Gauge::adjusted_value).
+    pub const fn with_baseline(baseline: i64) -> Self {
+        Self {
+            value: AtomicI64::new(0),
+            baseline: AtomicI64::new(baseline),
+            #[cfg(feature = "last-updated")]
+            updated: LastUpdated::new(),
+        }
     }
 
     /// Increment the value of this gauge by 1.
@@ -60,7 +97,7 @@ impl Gauge {
     /// Returns the od value of the gauge.
     #[inline]
     pub fn add(&self, value: i64) -> i64 {
-        self.0.fetch_add(value, Ordering::Relaxed)
+        self.add_ordered(value, Ordering::Relaxed)
     }
 
     /// Decrease the value of this gauge by `value`.
@@ -68,23 +105,97 @@ impl Gauge {
     /// Returns the od value of the gauge.
     #[inline]
     pub fn sub(&self, value: i64) -> i64 {
-        self.0.fetch_sub(value, Ordering::Relaxed)
+        self.sub_ordered(value, Ordering::Relaxed)
     }
 
     #[inline]
     pub fn value(&self) -> i64 {
-        self.0.load(Ordering::Relaxed)
+        self.value_ordered(Ordering::Relaxed)
+    }
+
+    /// Returns the value of this gauge minus its baseline. This is the value
+    /// reported by exporters, while `value` always returns the raw value.
+    #[inline]
+    pub fn adjusted_value(&self) -> i64 {
+        self.value().wrapping_sub(self.baseline())
+    }
+
+    /// Returns the baseline of this gauge.
+    #[inline]
+    pub fn baseline(&self) -> i64 {
+        self.baseline.load(Ordering::Relaxed)
+    }
+
+    /// Set the baseline of this gauge. This does not change its value.
+    #[inline]
+    pub fn set_baseline(&self, baseline: i64) {
+        self.baseline.store(baseline, Ordering::Relaxed);
     }
 
     #[inline]
     pub fn set(&self, value: i64) -> i64 {
-        self.0.swap(value, Ordering::Relaxed)
+        self.set_ordered(value, Ordering::Relaxed)
     }
 
     #[inline]
     pub fn reset(&self) -> i64 {
         self.set(0)
     }
+
+    /// Increment the value of this gauge by 1 using the provided `Ordering`.
+    #[inline]
+    pub fn increment_ordered(&self, ordering: Ordering) -> i64 {
+        self.add_ordered(1, ordering)
+    }
+
+    /// Decrement the value of this gauge by 1 using the provided `Ordering`.
+    #[inline]
+    pub fn decrement_ordered(&self, ordering: Ordering) -> i64 {
+        self.sub_ordered(1, ordering)
+    }
+
+    /// Increase the value of this gauge by `value` using the provided
+    /// `Ordering`.
+    #[inline]
+    pub fn add_ordered(&self, value: i64, ordering: Ordering) -> i64 {
+        #[cfg(feature = "last-updated")]
+        self.updated.touch();
+        self.value.fetch_add(value, ordering)
+    }
+
+    /// Decrease the value of this gauge by `value` using the provided
+    /// `Ordering`.
+    #[inline]
+    pub fn sub_ordered(&self, value: i64, ordering: Ordering) -> i64 {
+        #[cfg(feature = "last-updated")]
+        self.updated.touch();
+        self.value.fetch_sub(value, ordering)
+    }
+
+    /// Read the value of this gauge using the provided `Ordering`, which must
+    /// not be `Release` or `AcqRel`.
+    #[inline]
+    pub fn value_ordered(&self, ordering: Ordering) -> i64 {
+        self.value.load(ordering)
+    }
+
+    /// Set the value of this gauge using the provided `Ordering`.
+    #[inline]
+    pub fn set_ordered(&self, value: i64, ordering: Ordering) -> i64 {
+        #[cfg(feature = "last-updated")]
+        self.updated.touch();
+        self.value.swap(value, ordering)
+    }
+
+    /// Returns the time this gauge was last written, or `None` if it has
+    /// not been written since it was created.
+    ///
+    /// The time is read from the cached clock, so it is only as precise as
+    /// the most recent call to `rustcommon_time::refresh_clock()`.
+    #[cfg(feature = "last-updated")]
+    pub fn last_updated(&self) -> Option<UnixInstant<Nanoseconds<u64>>> {
+        self.updated.get()
+    }
 }
 
 impl Metric for Gauge {
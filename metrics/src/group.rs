@@ -0,0 +1,124 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+
+/// A group of related metrics which can be updated and read as a consistent
+/// set.
+///
+/// Normally each metric is read independently, so reading several related
+/// metrics may observe some of them before a concurrent update and some of
+/// them after it. For metrics which must agree with each other (for example,
+/// hits and misses which sum to a total) the updates can be performed within
+/// [`MetricGroup::update`] and the reads within [`MetricGroup::read`]. The
+/// group uses a generation counter (a seqlock) so that reads are retried until
+/// they observe no concurrent update.
+///
+/// Updates within a group are serialized with respect to each other, so this
+/// should only be used for metrics where consistency is worth that cost.
+///
+/// # Example
+/// ```
+/// # use rustcommon_metrics::*;
+/// static CACHE: MetricGroup = MetricGroup::new();
+///
+/// #[metric(name = "cache/hit")]
+/// static HIT: Counter = Counter::new();
+///
+/// #[metric(name = "cache/total")]
+/// static TOTAL: Counter = Counter::new();
+///
+/// CACHE.update(|| {
+///     HIT.increment();
+///     TOTAL.increment();
+/// });
+///
+/// let (hit, total) = metrics().snapshot_consistent(&CACHE, || (HIT.value(), TOTAL.value()));
+/// assert_eq!(hit, total);
+/// ```
+pub struct MetricGroup {
+    generation: AtomicU64,
+}
+
+impl MetricGroup {
+    /// Create a new metric group.
+    pub const fn new() -> Self {
+        Self {
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    /// Perform updates to the metrics in this group as a single change. Reads
+    /// through [`MetricGroup::read`] will observe either all or none of the
+    /// updates made within the provided function.
+    pub fn update<T>(&self, f: impl FnOnce() -> T) -> T {
+        let _guard = UpdateGuard::new(self);
+        f()
+    }
+
+    /// Read the metrics in this group as a consistent set. The provided
+    /// function may be called multiple times if it races with an update and
+    /// should therefore not have side effects.
+    pub fn read<T>(&self, f: impl Fn() -> T) -> T {
+        loop {
+            let start = self.generation.load(Ordering::Acquire);
+            if start & 1 == 1 {
+                // an update is in progress
+                std::hint::spin_loop();
+                continue;
+            }
+
+            let result = f();
+
+            fence(Ordering::Acquire);
+            if self.generation.load(Ordering::Relaxed) == start {
+                return result;
+            }
+        }
+    }
+}
+
+impl Default for MetricGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Marks an update as in-progress for as long as it is held. The update is
+/// completed on drop so that a panic during an update does not leave the
+/// group permanently locked.
+struct UpdateGuard<'a> {
+    group: &'a MetricGroup,
+}
+
+impl<'a> UpdateGuard<'a> {
+    fn new(group: &'a MetricGroup) -> Self {
+        loop {
+            let current = group.generation.load(Ordering::Relaxed);
+            if current & 1 == 0
+                && group
+                    .generation
+                    .compare_exchange_weak(
+                        current,
+                        current + 1,
+                        Ordering::Acquire,
+                        Ordering::Relaxed,
+                    )
+                    .is_ok()
+            {
+                break;
+            }
+            std::hint::spin_loop();
+        }
+        // make sure the odd generation is visible before any of the updates
+        fence(Ordering::Release);
+        Self { group }
+    }
+}
+
+impl Drop for UpdateGuard<'_> {
+    fn drop(&mut self) {
+        self.group.generation.fetch_add(1, Ordering::Release);
+    }
+}
@@ -71,19 +71,38 @@ use std::any::Any;
 use std::borrow::Cow;
 
 mod counter;
+mod derived;
 mod gauge;
+mod group;
 mod heatmap;
 mod lazy;
+mod percentiles;
+mod snapshot;
+mod summarized;
+#[cfg(feature = "last-updated")]
+mod updated;
 
 extern crate self as rustcommon_metrics;
 
 pub mod dynmetrics;
+#[cfg(feature = "otel")]
+pub mod otel;
+#[cfg(feature = "test-util")]
+pub mod testing;
 
 pub use crate::counter::Counter;
-pub use crate::dynmetrics::{DynBoxedMetric, DynPinnedMetric};
+pub use crate::derived::DerivedMetric;
+pub use crate::dynmetrics::{
+    register_metric, DynBoxedMetric, DynMetricBuilder, DynPinnedMetric, RegisteredMetric,
+    ScopedMetric,
+};
 pub use crate::gauge::Gauge;
+pub use crate::group::MetricGroup;
 pub use crate::heatmap::Heatmap;
 pub use crate::lazy::{Lazy, Relaxed};
+pub use crate::percentiles::set_percentile_ttl;
+pub use crate::snapshot::Snapshotter;
+pub use crate::summarized::SummarizedCounter;
 
 pub use rustcommon_metrics_derive::metric;
 
@@ -99,11 +118,34 @@ pub mod export {
 
     #[linkme::distributed_slice]
     pub static METRICS: [crate::MetricEntry] = [..];
+
+    /// Builds the heatmap declared by the [`heatmap`](crate::heatmap) macro.
+    pub fn heatmap(
+        max: u64,
+        span: Duration<Nanoseconds<u64>>,
+        resolution: Duration<Nanoseconds<u64>>,
+    ) -> crate::Heatmap {
+        crate::Heatmap::builder()
+            .maximum_value(max)
+            .min_resolution(1)
+            .min_resolution_range(1024)
+            .span(span)
+            .resolution(resolution)
+            .build()
+            .expect("bad heatmap configuration")
+    }
 }
 
 #[macro_export]
 #[rustfmt::skip]
 macro_rules! counter {
+    (@prefix $prefix:literal, $name:ident) => {
+        #[$crate::metric(
+            name = concat!($prefix, ".", $crate::to_lowercase!($name)),
+            crate = $crate
+        )]
+        pub static $name: $crate::Counter = $crate::Counter::new();
+    };
     ($name:ident) => {
         #[$crate::metric(
             name = $crate::to_lowercase!($name),
@@ -121,6 +163,27 @@ macro_rules! counter {
     };
 }
 
+/// Declare a group of related counters which share a common prefix. Each
+/// counter is named `<prefix>.<name>` with the name lowercased.
+///
+/// ```
+/// # use rustcommon_metrics::*;
+/// metric_group!("cache", [HIT, MISS, EVICT]);
+///
+/// HIT.increment();
+/// assert_eq!(HIT.name(), "cache.hit");
+/// assert_eq!(EVICT.name(), "cache.evict");
+/// ```
+#[macro_export]
+#[rustfmt::skip]
+macro_rules! metric_group {
+    ($prefix:literal, [$($name:ident),* $(,)?]) => {
+        $(
+            $crate::counter!(@prefix $prefix, $name);
+        )*
+    };
+}
+
 #[macro_export]
 #[rustfmt::skip]
 macro_rules! gauge {
@@ -141,40 +204,62 @@ macro_rules! gauge {
     };
 }
 
+/// Declare a heatmap which covers the past minute with a resolution of one
+/// second. The span and resolution may instead be provided, for example, from
+/// durations parsed from a configuration file. They are only evaluated when the
+/// heatmap is first used.
+///
+/// ```
+/// # use rustcommon_metrics::*;
+/// # use rustcommon_metrics::time::{Duration, Nanoseconds};
+/// heatmap!(REQUEST_LATENCY, 1_000_000_000);
+///
+/// fn span() -> Duration<Nanoseconds<u64>> {
+///     // e.g. read from a config file
+///     "5m".parse().expect("invalid span")
+/// }
+///
+/// heatmap!(QUEUE_LATENCY, 1_000_000_000, span(), "10s".parse().unwrap());
+///
+/// assert_eq!(QUEUE_LATENCY.windows(), 30);
+/// ```
 #[macro_export]
 #[rustfmt::skip]
 macro_rules! heatmap {
     ($name:ident, $max:expr) => {
+        $crate::heatmap!(
+            $name,
+            $max,
+            $crate::export::Duration::<$crate::export::Nanoseconds<u64>>::from_secs(60),
+            $crate::export::Duration::<$crate::export::Nanoseconds<u64>>::from_secs(1)
+        );
+    };
+    ($name:ident, $max:expr, $description:tt) => {
+        $crate::heatmap!(
+            $name,
+            $max,
+            $crate::export::Duration::<$crate::export::Nanoseconds<u64>>::from_secs(60),
+            $crate::export::Duration::<$crate::export::Nanoseconds<u64>>::from_secs(1),
+            $description
+        );
+    };
+    ($name:ident, $max:expr, $span:expr, $resolution:expr) => {
         #[$crate::metric(
             name = $crate::to_lowercase!($name),
             crate = $crate
         )]
         pub static $name: $crate::Relaxed<$crate::Heatmap> = $crate::Relaxed::new(|| {
-            $crate::Heatmap::builder()
-                .maximum_value($max as _)
-                .min_resolution(1)
-                .min_resolution_range(1024)
-                .span($crate::export::Duration::<$crate::export::Nanoseconds<u64>>::from_secs(60))
-                .resolution($crate::export::Duration::<$crate::export::Nanoseconds<u64>>::from_secs(1))
-                .build()
-                .expect("bad heatmap configuration")
+            $crate::export::heatmap($max as _, $span, $resolution)
         });
     };
-    ($name:ident, $max:expr, $description:tt) => {
+    ($name:ident, $max:expr, $span:expr, $resolution:expr, $description:tt) => {
         #[$crate::metric(
             name = $crate::to_lowercase!($name),
             description = $description,
             crate = $crate
         )]
         pub static $name: $crate::Relaxed<$crate::Heatmap> = $crate::Relaxed::new(|| {
-            $crate::Heatmap::builder()
-                .maximum_value($max as _)
-                .min_resolution(1)
-                .min_resolution_range(1024)
-                .span($crate::export::Duration::<$crate::export::Nanoseconds<u64>>::from_secs(60))
-                .resolution($crate::export::Duration::<$crate::export::Nanoseconds<u64>>::from_secs(1))
-                .build()
-                .expect("bad heatmap configuration")
+            $crate::export::heatmap($max as _, $span, $resolution)
         });
     };
 }
@@ -202,8 +287,12 @@ pub trait Metric: Send + Sync + 'static {
 pub struct MetricEntry {
     metric: MetricWrapper,
     name: Cow<'static, str>,
-    namespace: Option<&'static str>,
-    description: Option<&'static str>,
+    namespace: Option<Cow<'static, str>>,
+    description: Option<Cow<'static, str>>,
+    hidden: bool,
+    metadata: &'static [(&'static str, &'static str)],
+    // assigned when a dynamic metric is registered, unused for static metrics
+    id: Option<u64>,
 }
 
 impl MetricEntry {
@@ -213,22 +302,27 @@ impl MetricEntry {
         name: &'static str,
         namespace: &'static str,
         description: &'static str,
+        hidden: bool,
+        metadata: &'static [(&'static str, &'static str)],
     ) -> Self {
         let namespace = if namespace.is_empty() {
             None
         } else {
-            Some(namespace)
+            Some(Cow::Borrowed(namespace))
         };
         let description = if description.is_empty() {
             None
         } else {
-            Some(description)
+            Some(Cow::Borrowed(description))
         };
         Self {
             metric,
             name: Cow::Borrowed(name),
             namespace,
             description,
+            hidden,
+            metadata,
+            id: None,
         }
     }
 
@@ -250,6 +344,9 @@ impl MetricEntry {
             name,
             namespace: None,
             description: None,
+            hidden: false,
+            metadata: &[],
+            id: None,
         }
     }
 
@@ -258,6 +355,26 @@ impl MetricEntry {
         unsafe { &*self.metric.0 }
     }
 
+    /// Get the id of this metric, which can be used with [`Metrics::get_by_id`]
+    /// to look up the metric again without comparing names, for example, to
+    /// cache the metrics an exporter reports across scrapes.
+    ///
+    /// The id of a static metric is stable within a build of the program. The
+    /// id of a dynamic metric is assigned when it is registered and is stable
+    /// while it remains registered. Ids are never reused within a process.
+    /// Returns `None` for an entry which has not been registered.
+    pub fn id(&self) -> Option<u64> {
+        let statics = &*crate::export::METRICS;
+        let start = statics.as_ptr() as usize;
+        let end = start + std::mem::size_of_val(statics);
+        let address = self as *const Self as usize;
+        if (start..end).contains(&address) {
+            Some(((address - start) / std::mem::size_of::<Self>()) as u64)
+        } else {
+            self.id
+        }
+    }
+
     /// Get the name of this metric.
     pub fn name(&self) -> &str {
         &*self.name
@@ -265,12 +382,111 @@ impl MetricEntry {
 
     /// Get the namespace of this metric.
     pub fn namespace(&self) -> Option<&str> {
-        self.namespace
+        self.namespace.as_deref()
+    }
+
+    /// Get the name of this metric prefixed by its namespace, if it has one,
+    /// using the provided `separator`. For example, a metric named `requests`
+    /// in the `server` namespace has the full name `server.requests` with a
+    /// separator of `"."`.
+    ///
+    /// Exporters should use this to compose the exported name of a metric so
+    /// that the naming is consistent across exporters.
+    pub fn full_name(&self, separator: &str) -> String {
+        match self.namespace() {
+            Some(namespace) => format!("{}{}{}", namespace, separator, self.name()),
+            None => self.name().to_string(),
+        }
     }
 
     /// Get the description of this metric.
     pub fn description(&self) -> Option<&str> {
-        self.description
+        self.description.as_deref()
+    }
+
+    /// Get the freeform metadata attached to this metric as key-value pairs,
+    /// for example, `("aggregation", "sum")`. Exporters can consult this for
+    /// backend-specific hints.
+    pub fn metadata(&self) -> &'static [(&'static str, &'static str)] {
+        self.metadata
+    }
+
+    /// Look up the value for a metadata `key` of this metric.
+    pub fn metadata_value(&self, key: &str) -> Option<&'static str> {
+        self.metadata
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+    }
+
+    /// Set the namespace of this metric.
+    pub fn with_namespace(mut self, namespace: impl Into<Cow<'static, str>>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Set the description of this metric.
+    pub fn with_description(mut self, description: impl Into<Cow<'static, str>>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Set the freeform metadata of this metric.
+    pub fn with_metadata(mut self, metadata: &'static [(&'static str, &'static str)]) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Set whether this metric is hidden from default exports.
+    pub fn set_hidden(&mut self, hidden: bool) {
+        self.hidden = hidden;
+    }
+
+    /// Returns `true` if this metric should be hidden from default exports,
+    /// for example, because it is deprecated but kept for compatibility.
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    /// Returns the time this metric was last written, or `None` if it has
+    /// not been written or is not a [`Counter`] or [`Gauge`]. Exporters can use
+    /// this to detect metrics which have stopped being updated.
+    #[cfg(feature = "last-updated")]
+    pub fn last_updated(&self) -> Option<time::UnixInstant<time::Nanoseconds<u64>>> {
+        let any = self.as_any()?;
+        if let Some(counter) = any.downcast_ref::<Counter>() {
+            counter.last_updated()
+        } else if let Some(gauge) = any.downcast_ref::<Gauge>() {
+            gauge.last_updated()
+        } else {
+            None
+        }
+    }
+
+    /// Reset this metric, for example, from admin tooling after a
+    /// configuration change invalidates the data recorded so far. A
+    /// [`Counter`] or [`Gauge`] is reset to zero, a [`Heatmap`] is cleared, and
+    /// a [`SummarizedCounter`] is reset to zero and its summary is cleared.
+    ///
+    /// Returns `false` if the metric is not one of these types, or has not
+    /// been initialized, in which case it is left unchanged.
+    pub fn reset(&self) -> bool {
+        let any = match self.as_any() {
+            Some(any) => any,
+            None => return false,
+        };
+        if let Some(counter) = any.downcast_ref::<Counter>() {
+            counter.reset();
+        } else if let Some(gauge) = any.downcast_ref::<Gauge>() {
+            gauge.reset();
+        } else if let Some(heatmap) = any.downcast_ref::<Heatmap>() {
+            heatmap.clear();
+        } else if let Some(counter) = any.downcast_ref::<SummarizedCounter>() {
+            counter.reset();
+        } else {
+            return false;
+        }
+        true
     }
 }
 
@@ -341,6 +557,50 @@ impl Metrics {
     pub fn iter(&self) -> <&Self as IntoIterator>::IntoIter {
         self.into_iter()
     }
+
+    /// Iterate over only the metrics that were registered via the [`metric`]
+    /// attribute macro.
+    pub fn iter_static(&self) -> std::slice::Iter<'static, MetricEntry> {
+        self.static_metrics().iter()
+    }
+
+    /// Iterate over only the metrics that were dynamically registered.
+    pub fn iter_dynamic(&self) -> std::slice::Iter<'_, MetricEntry> {
+        self.dynamic_metrics().iter()
+    }
+
+    /// Iterate over all metrics which are not hidden. Exporters should use
+    /// this by default and only include hidden metrics when asked to.
+    pub fn visible(&self) -> impl Iterator<Item = &MetricEntry> {
+        self.iter().filter(|entry| !entry.is_hidden())
+    }
+
+    /// Look up a metric by its [`id`](MetricEntry::id). Returns `None` if
+    /// there is no such metric, for example, because it was unregistered.
+    pub fn get_by_id(&self, id: u64) -> Option<&MetricEntry> {
+        let statics = self.static_metrics();
+        match statics.get(id as usize) {
+            Some(entry) => Some(entry),
+            None => self.dyn_metrics.get_by_id(id),
+        }
+    }
+
+    /// Reset every resettable metric with the provided `name`. See
+    /// [`MetricEntry::reset`] for which metrics can be reset. Returns the
+    /// number of metrics which were reset.
+    pub fn reset(&self, name: &str) -> usize {
+        self.iter()
+            .filter(|entry| entry.name() == name)
+            .filter(|entry| entry.reset())
+            .count()
+    }
+
+    /// Read a set of related metrics which are updated through the provided
+    /// [`MetricGroup`] such that the values are consistent with each other.
+    /// See [`MetricGroup::read`] for details.
+    pub fn snapshot_consistent<T>(&self, group: &MetricGroup, f: impl Fn() -> T) -> T {
+        group.read(f)
+    }
 }
 
 impl<'a> IntoIterator for &'a Metrics {
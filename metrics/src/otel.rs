@@ -0,0 +1,264 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+//! Conversion of the registered metrics into OpenTelemetry (OTLP) metric data.
+//!
+//! The types in this module mirror the shape of the messages defined in
+//! `opentelemetry/proto/metrics/v1/metrics.proto` so that they can be mapped
+//! directly onto the types of whichever OTLP exporter is in use, without this
+//! crate depending on a particular protobuf implementation.
+//!
+//! Metrics are converted as follows:
+//! - [`Counter`]s and [`SummarizedCounter`]s become a monotonic, cumulative
+//!   [`Sum`] with an integer value. Values above `i64::MAX` are reported as
+//!   `i64::MAX`.
+//! - [`Gauge`]s become a [`Gauge`](struct@Gauge) with an integer value,
+//!   which is the value relative to the baseline of the gauge.
+//! - [`DerivedMetric`]s using a function pointer become a
+//!   [`Gauge`](struct@Gauge) with a double value.
+//! - [`Heatmap`]s become a delta [`Histogram`] with one data point for each
+//!   window of the heatmap which has completed since the previous export by
+//!   the same [`OtlpExporter`], and one explicit bound for the upper edge of
+//!   each bucket. The windows do not overlap, so summing the deltas counts
+//!   each value once. Windows without any counts are skipped.
+//!
+//! Metrics of any other type, and metrics which are not yet enabled, are
+//! skipped.
+//!
+//! The name of each metric is its [full name](MetricEntry::full_name), with
+//! the namespace and name joined by a `.`.
+//!
+//! [`Counter`]: crate::Counter
+//! [`Gauge`]: crate::Gauge
+//! [`SummarizedCounter`]: crate::SummarizedCounter
+//! [`DerivedMetric`]: crate::DerivedMetric
+//! [`Heatmap`]: crate::Heatmap
+
+use crate::{MetricEntry, Metrics};
+use rustcommon_time::{Instant, Nanoseconds, UnixInstant};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A single metric, corresponding to the OTLP `Metric` message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metric {
+    pub name: String,
+    pub description: String,
+    pub unit: String,
+    pub data: Data,
+}
+
+/// The data of a metric, corresponding to the `data` field of the OTLP
+/// `Metric` message.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Data {
+    Gauge(Gauge),
+    Sum(Sum),
+    Histogram(Histogram),
+}
+
+/// Corresponds to the OTLP `AggregationTemporality` enum.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AggregationTemporality {
+    Unspecified,
+    Delta,
+    Cumulative,
+}
+
+/// Corresponds to the OTLP `Gauge` message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gauge {
+    pub data_points: Vec<NumberDataPoint>,
+}
+
+/// Corresponds to the OTLP `Sum` message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sum {
+    pub data_points: Vec<NumberDataPoint>,
+    pub aggregation_temporality: AggregationTemporality,
+    pub is_monotonic: bool,
+}
+
+/// Corresponds to the OTLP `Histogram` message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    pub data_points: Vec<HistogramDataPoint>,
+    pub aggregation_temporality: AggregationTemporality,
+}
+
+/// The value of a `NumberDataPoint`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value {
+    AsDouble(f64),
+    AsInt(i64),
+}
+
+/// Corresponds to the OTLP `NumberDataPoint` message. Times are nanoseconds
+/// since the UNIX epoch, with a start time of zero meaning it is unknown.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NumberDataPoint {
+    pub start_time_unix_nano: u64,
+    pub time_unix_nano: u64,
+    pub value: Value,
+}
+
+/// Corresponds to the OTLP `HistogramDataPoint` message. Times are nanoseconds
+/// since the UNIX epoch.
+///
+/// As in OTLP, `bucket_counts` has one more element than `explicit_bounds`.
+/// The bucket at index `i` counts values in `(explicit_bounds[i - 1],
+/// explicit_bounds[i]]`, with the final bucket counting values above the last
+/// bound.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistogramDataPoint {
+    pub start_time_unix_nano: u64,
+    pub time_unix_nano: u64,
+    pub count: u64,
+    pub bucket_counts: Vec<u64>,
+    pub explicit_bounds: Vec<f64>,
+}
+
+/// Converts the registered metrics into OTLP metric data. See the
+/// [`otel`](crate::otel) module for how each metric type is converted.
+///
+/// Heatmaps are exported as deltas, so the exporter keeps track of the windows
+/// which it has already reported. Each consumer of the metrics, such as each
+/// OTLP endpoint, should use its own `OtlpExporter` and keep it between
+/// exports.
+#[derive(Default)]
+pub struct OtlpExporter {
+    verbose: bool,
+    // the end of the newest heatmap window which has been exported, keyed by
+    // the id of the metric
+    exported: BTreeMap<u64, Instant<Nanoseconds<u64>>>,
+}
+
+impl OtlpExporter {
+    /// Create an exporter which has not yet exported any heatmap windows.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether hidden metrics are exported. Defaults to `false`.
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Convert the registered metrics into OTLP metric data.
+    pub fn export(&mut self, metrics: &Metrics) -> Vec<Metric> {
+        let now = unix_nanos(UnixInstant::<Nanoseconds<u64>>::now());
+        let mut present = BTreeSet::new();
+        let mut result = Vec::new();
+        for entry in metrics.iter() {
+            present.extend(entry.id());
+            if !self.verbose && entry.is_hidden() {
+                continue;
+            }
+            if let Some(metric) = self.convert(entry, now) {
+                result.push(metric);
+            }
+        }
+        // forget the heatmaps which have since been unregistered
+        self.exported.retain(|id, _| present.contains(id));
+        result
+    }
+
+    fn convert(&mut self, entry: &MetricEntry, now: u64) -> Option<Metric> {
+        let any = entry.as_any()?;
+
+        let data = if let Some(counter) = any.downcast_ref::<crate::Counter>() {
+            counter_sum(counter.value(), now)
+        } else if let Some(counter) = any.downcast_ref::<crate::SummarizedCounter>() {
+            counter_sum(counter.value(), now)
+        } else if let Some(gauge) = any.downcast_ref::<crate::Gauge>() {
+            Data::Gauge(Gauge {
+                data_points: vec![NumberDataPoint {
+                    start_time_unix_nano: 0,
+                    time_unix_nano: now,
+                    value: Value::AsInt(gauge.adjusted_value()),
+                }],
+            })
+        } else if let Some(derived) = any.downcast_ref::<crate::DerivedMetric>() {
+            Data::Gauge(Gauge {
+                data_points: vec![NumberDataPoint {
+                    start_time_unix_nano: 0,
+                    time_unix_nano: now,
+                    value: Value::AsDouble(derived.value()),
+                }],
+            })
+        } else if let Some(heatmap) = any.downcast_ref::<crate::Heatmap>() {
+            // every entry iterated from the registry has an id
+            self.heatmap_histogram(entry.id()?, heatmap)
+        } else {
+            return None;
+        };
+
+        Some(Metric {
+            name: entry.full_name("."),
+            description: entry.description().unwrap_or_default().to_string(),
+            unit: String::new(),
+            data,
+        })
+    }
+
+    fn heatmap_histogram(&mut self, id: u64, heatmap: &crate::Heatmap) -> Data {
+        let now = Instant::<Nanoseconds<u64>>::now();
+        let previous = self.exported.get(&id).copied();
+        let mut data_points = Vec::new();
+
+        for window in heatmap {
+            // only report windows which are complete and which were not
+            // reported by a previous export
+            if window.stop() > now || previous.is_some_and(|p| window.start() < p) {
+                continue;
+            }
+            self.exported.insert(id, window.stop());
+
+            let mut explicit_bounds = Vec::new();
+            // the final bucket counts values above the last bound, which a
+            // heatmap never contains
+            let mut bucket_counts = Vec::new();
+            for bucket in window.histogram() {
+                explicit_bounds.push(bucket.high() as f64);
+                bucket_counts.push(u64::from(bucket.count()));
+            }
+            bucket_counts.push(0);
+
+            let count = bucket_counts.iter().sum();
+            if count == 0 {
+                continue;
+            }
+            data_points.push(HistogramDataPoint {
+                start_time_unix_nano: unix_nanos(window.start().to_unix()),
+                time_unix_nano: unix_nanos(window.stop().to_unix()),
+                count,
+                bucket_counts,
+                explicit_bounds,
+            });
+        }
+
+        Data::Histogram(Histogram {
+            data_points,
+            aggregation_temporality: AggregationTemporality::Delta,
+        })
+    }
+}
+
+fn counter_sum(value: u64, now: u64) -> Data {
+    Data::Sum(Sum {
+        data_points: vec![NumberDataPoint {
+            start_time_unix_nano: 0,
+            time_unix_nano: now,
+            value: Value::AsInt(i64::try_from(value).unwrap_or(i64::MAX)),
+        }],
+        aggregation_temporality: AggregationTemporality::Cumulative,
+        is_monotonic: true,
+    })
+}
+
+fn unix_nanos(instant: UnixInstant<Nanoseconds<u64>>) -> u64 {
+    instant
+        .duration_since(UnixInstant::<Nanoseconds<u64>>::from_nanos(0))
+        .as_nanos()
+}
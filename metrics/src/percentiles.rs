@@ -0,0 +1,117 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use crate::{Heatmap, MetricEntry, Metrics};
+use parking_lot::{const_mutex, Mutex};
+use rustcommon_time::{Duration, Instant, Nanoseconds};
+use std::collections::BTreeMap;
+
+/// The default time for which heatmap percentiles are cached.
+const DEFAULT_TTL: Duration<Nanoseconds<u64>> =
+    Duration::<Nanoseconds<u64>>::from_nanos(1_000_000_000);
+
+static CACHE: Mutex<PercentileCache> = const_mutex(PercentileCache::new());
+
+/// Caches the percentiles calculated for each heatmap, keyed by the id of the
+/// metric. Ids are never reused, so a heatmap which is registered after
+/// another is unregistered can't be served its percentiles.
+struct PercentileCache {
+    ttl: Option<Duration<Nanoseconds<u64>>>,
+    entries: BTreeMap<u64, CachedPercentiles>,
+}
+
+struct CachedPercentiles {
+    calculated: Instant<Nanoseconds<u64>>,
+    percentiles: Vec<f64>,
+    values: Option<Vec<(f64, u64)>>,
+}
+
+impl PercentileCache {
+    const fn new() -> Self {
+        Self {
+            ttl: None,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    fn ttl(&self) -> Duration<Nanoseconds<u64>> {
+        self.ttl.unwrap_or(DEFAULT_TTL)
+    }
+}
+
+/// Sets how long the percentiles calculated by [`Metrics::heatmap_percentiles`]
+/// are reused before being calculated again. The default is one second. A
+/// zero duration disables caching.
+pub fn set_percentile_ttl(ttl: Duration<Nanoseconds<u64>>) {
+    let mut cache = CACHE.lock();
+    cache.ttl = Some(ttl);
+    cache.entries.clear();
+}
+
+impl Metrics {
+    /// Calculate the requested percentiles for a heatmap metric, returning
+    /// pairs of the percentile and the upper edge of the bucket which contains
+    /// it. Returns `None` if the metric is not a heatmap or the heatmap is
+    /// empty.
+    ///
+    /// Calculating percentiles requires walking every bucket of the heatmap,
+    /// which is expensive when exporting many heatmaps. The results are
+    /// cached and reused until the configured TTL elapses (see
+    /// [`set_percentile_ttl`]), so rapid repeated exports only calculate them
+    /// once. New increments do not invalidate the cache, so the results may be
+    /// up to the TTL out of date.
+    pub fn heatmap_percentiles(
+        &self,
+        entry: &MetricEntry,
+        percentiles: &[f64],
+    ) -> Option<Vec<(f64, u64)>> {
+        let heatmap = entry.as_any()?.downcast_ref::<Heatmap>()?;
+        let key = entry.id();
+        let now = Instant::<Nanoseconds<u64>>::now();
+
+        {
+            let cache = CACHE.lock();
+            if let Some(cached) = key.and_then(|key| cache.entries.get(&key)) {
+                if cached.percentiles == percentiles && now - cached.calculated < cache.ttl() {
+                    return cached.values.clone();
+                }
+            }
+        }
+
+        // the lock is not held while walking the buckets, so exports of other
+        // heatmaps are not blocked. concurrent exports of the same heatmap may
+        // both calculate the percentiles, in which case the last one is kept.
+        let values: Option<Vec<(f64, u64)>> = percentiles
+            .iter()
+            .map(|p| heatmap.percentile(*p).ok().map(|b| (*p, b.high())))
+            .collect();
+
+        // entries which have not been registered can't be cached
+        let key = match key {
+            Some(key) => key,
+            None => return values,
+        };
+
+        // the TTL may have changed while calculating
+        let mut cache = CACHE.lock();
+        let ttl = cache.ttl();
+        if ttl > Duration::<Nanoseconds<u64>>::from_nanos(0) {
+            // drop entries which have expired, including those for metrics
+            // which are no longer registered
+            cache
+                .entries
+                .retain(|_, cached| now - cached.calculated < ttl);
+            cache.entries.insert(
+                key,
+                CachedPercentiles {
+                    calculated: now,
+                    percentiles: percentiles.to_vec(),
+                    values: values.clone(),
+                },
+            );
+        }
+
+        values
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use crate::{Counter, Heatmap};
+use parking_lot::Mutex;
+use rustcommon_time::{Instant, Nanoseconds};
+
+/// Periodically records the rate of change of counters into heatmaps.
+///
+/// Each counter is paired with a heatmap. On every call to [`tick`], the
+/// change in each counter since the previous tick is converted into a
+/// per-second rate and recorded into the associated heatmap at the time of the
+/// tick. This allows the distribution of a counter's rate to be reported using
+/// the heatmap percentiles.
+///
+/// The first tick for a counter only records its value, as there is no
+/// earlier reading to compute a rate from. Ticks which are not later than the
+/// previous tick are ignored.
+///
+/// [`tick`]: Snapshotter::tick
+///
+/// # Example
+/// ```
+/// # use rustcommon_metrics::{Counter, Heatmap, Snapshotter};
+/// # use rustcommon_metrics::time::{Duration, Instant, Nanoseconds};
+/// static REQUESTS: Counter = Counter::new();
+///
+/// let heatmap = Heatmap::builder()
+///     .maximum_value(1_000_000)
+///     .build()
+///     .expect("bad heatmap configuration");
+///
+/// let mut snapshotter = Snapshotter::new();
+/// snapshotter.add(&REQUESTS, &heatmap);
+///
+/// let start = Instant::<Nanoseconds<u64>>::now();
+/// snapshotter.tick(start);
+///
+/// REQUESTS.add(100);
+/// snapshotter.tick(start + Duration::<Nanoseconds<u64>>::from_secs(1));
+///
+/// let bucket = heatmap.percentile(100.0).unwrap();
+/// assert!(bucket.low() <= 100 && 100 <= bucket.high());
+/// ```
+#[derive(Default)]
+pub struct Snapshotter<'a> {
+    entries: Vec<Entry<'a>>,
+}
+
+struct Entry<'a> {
+    counter: &'a Counter,
+    heatmap: &'a Heatmap,
+    previous: Mutex<Option<(Instant<Nanoseconds<u64>>, u64)>>,
+}
+
+impl<'a> Snapshotter<'a> {
+    /// Create a new snapshotter without any counters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the rate of `counter` into `heatmap` on each tick.
+    pub fn add(&mut self, counter: &'a Counter, heatmap: &'a Heatmap) {
+        self.entries.push(Entry {
+            counter,
+            heatmap,
+            previous: Mutex::new(None),
+        });
+    }
+
+    /// Returns the number of counters which are recorded by this snapshotter.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if this snapshotter has no counters.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Read each counter and record the per-second rate since the previous
+    /// tick into its heatmap.
+    pub fn tick(&self, now: Instant<Nanoseconds<u64>>) {
+        for entry in &self.entries {
+            let value = entry.counter.value();
+            let mut previous = entry.previous.lock();
+
+            if let Some((time, last)) = *previous {
+                let elapsed = match now.checked_duration_since(time) {
+                    Some(elapsed) if elapsed.as_nanos() > 0 => elapsed.as_nanos(),
+                    _ => continue,
+                };
+                // counters wrap on overflow, so the delta does too
+                let delta = value.wrapping_sub(last);
+                let rate = (delta as u128 * 1_000_000_000 / elapsed as u128)
+                    .try_into()
+                    .unwrap_or(u64::MAX);
+                let _ = entry.heatmap.increment(now, rate, 1);
+            }
+
+            *previous = Some((now, value));
+        }
+    }
+}
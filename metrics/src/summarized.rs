@@ -0,0 +1,139 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use crate::{Counter, Heatmap, Metric, Relaxed};
+use parking_lot::{const_mutex, Mutex};
+use rustcommon_time::{Instant, Nanoseconds};
+use std::any::Any;
+
+/// A counter with an attached summary which tracks the distribution of its
+/// rate of change.
+///
+/// The counter itself behaves like a [`Counter`]. Each call to [`tick`]
+/// records the rate (per second) since the previous tick into a [`Heatmap`],
+/// allowing percentiles of the rate to be reported for a static counter. The
+/// resolution of the distribution is determined by the interval between ticks,
+/// so `tick` should be called by a single owner on a fixed interval, for
+/// example, by the exporter just before it exports the metrics. Reading the
+/// summary or its percentiles does not record a rate, so any number of readers
+/// can do so without affecting the distribution.
+///
+/// [`tick`]: SummarizedCounter::tick
+///
+/// # Example
+/// ```
+/// # use rustcommon_metrics::{metric, Heatmap, SummarizedCounter};
+/// # use rustcommon_metrics::time::{Instant, Nanoseconds};
+/// #[metric(name = "requests")]
+/// static REQUESTS: SummarizedCounter = SummarizedCounter::new(|| {
+///     Heatmap::builder().build().expect("bad heatmap configuration")
+/// });
+///
+/// fn a_method() {
+///     REQUESTS.increment();
+///     // ...
+/// }
+/// # a_method();
+/// // called periodically, for example, before exporting the metrics
+/// REQUESTS.tick(Instant::<Nanoseconds<u64>>::now());
+/// # let _ = REQUESTS.percentile(50.0);
+/// ```
+pub struct SummarizedCounter<F = fn() -> Heatmap> {
+    counter: Counter,
+    summary: Relaxed<Heatmap, F>,
+    previous: Mutex<Option<(Instant<Nanoseconds<u64>>, u64)>>,
+}
+
+impl<F> SummarizedCounter<F> {
+    /// Create a counter initialized to 0 with a summary that will be
+    /// constructed by the provided function on first use.
+    pub const fn new(summary: F) -> Self {
+        Self {
+            counter: Counter::new(),
+            summary: Relaxed::new(summary),
+            previous: const_mutex(None),
+        }
+    }
+
+    /// Increment the counter by 1, returning the previous value.
+    #[inline]
+    pub fn increment(&self) -> u64 {
+        self.counter.increment()
+    }
+
+    /// Add `value` to the counter, returning the previous value.
+    #[inline]
+    pub fn add(&self, value: u64) -> u64 {
+        self.counter.add(value)
+    }
+
+    /// Read the value of the counter.
+    #[inline]
+    pub fn value(&self) -> u64 {
+        self.counter.value()
+    }
+
+    /// Reset the counter to 0 and clear the summary, returning the previous
+    /// value of the counter. The next tick only records the value of the
+    /// counter, as with the first tick.
+    pub fn reset(&self) -> u64 {
+        let mut previous = self.previous.lock();
+        *previous = None;
+        if let Some(summary) = Relaxed::get(&self.summary) {
+            summary.clear();
+        }
+        self.counter.reset()
+    }
+}
+
+impl<F: FnOnce() -> Heatmap> SummarizedCounter<F> {
+    /// Record the per-second rate of the counter since the previous tick into
+    /// the summary, at the provided time.
+    ///
+    /// The first tick only records the value of the counter, as there is no
+    /// earlier reading to compute a rate from. Ticks which are not later than
+    /// the previous tick are ignored.
+    pub fn tick(&self, now: Instant<Nanoseconds<u64>>) {
+        let summary = Relaxed::force(&self.summary);
+        let value = self.counter.value();
+        let mut previous = self.previous.lock();
+
+        if let Some((time, last)) = *previous {
+            let elapsed = match now.checked_duration_since(time) {
+                Some(elapsed) if elapsed.as_nanos() > 0 => elapsed.as_nanos(),
+                _ => return,
+            };
+            // counters wrap on overflow, so the delta does too
+            let delta = value.wrapping_sub(last);
+            let rate = (delta as u128 * 1_000_000_000 / elapsed as u128)
+                .try_into()
+                .unwrap_or(u64::MAX);
+            let _ = summary.increment(now, rate, 1);
+        }
+
+        *previous = Some((now, value));
+    }
+
+    /// Returns a reference to the summary of the rates recorded by `tick`.
+    pub fn summary(&self) -> &Heatmap {
+        Relaxed::force(&self.summary)
+    }
+
+    /// Returns the nearest rate for the requested percentile (0.0 - 100.0) of
+    /// the rates recorded by `tick`.
+    pub fn percentile(&self, percentile: f64) -> Result<u64, heatmap::Error> {
+        self.summary()
+            .percentile(percentile)
+            .map(|bucket| bucket.high())
+    }
+}
+
+impl<F> Metric for SummarizedCounter<F>
+where
+    F: (FnOnce() -> Heatmap) + Send + 'static,
+{
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+}
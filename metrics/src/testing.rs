@@ -0,0 +1,77 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+//! Support for tests which make assertions about the registered metrics.
+//!
+//! All metrics are registered into a single process-wide registry, so a test
+//! which inspects [`metrics`](crate::metrics) will also observe metrics that
+//! were registered by other tests, possibly running concurrently. A
+//! [`RegistryScope`] snapshots the registry when it is created and then only
+//! yields the metrics which were registered after that point.
+//!
+//! Static metrics are all registered before `main` runs and so never appear
+//! within a scope. Tests which need to inspect static metrics should instead
+//! filter them by name.
+
+use crate::{MetricEntry, Metrics};
+use parking_lot::{const_mutex, Mutex, MutexGuard};
+use std::collections::HashSet;
+
+// Serializes scopes so that metrics registered by concurrently running tests
+// don't leak into each other.
+static SCOPE_LOCK: Mutex<()> = const_mutex(());
+
+/// A guard which isolates the metrics registered while it is held.
+///
+/// Only one `RegistryScope` may exist at a time. Creating a second scope
+/// blocks until the first is dropped, so tests using scopes are serialized
+/// with respect to each other. Tests which register dynamic metrics without a
+/// scope may still show up in a concurrently held scope.
+///
+/// # Example
+/// ```
+/// # use rustcommon_metrics::*;
+/// # use rustcommon_metrics::testing::RegistryScope;
+/// let scope = RegistryScope::new();
+/// let _counter = DynBoxedMetric::new(Counter::new(), "scoped.counter");
+///
+/// let metrics = metrics();
+/// let names: Vec<_> = scope.metrics(&metrics).map(|entry| entry.name()).collect();
+/// assert_eq!(names, ["scoped.counter"]);
+/// ```
+pub struct RegistryScope {
+    // the ids of the metrics which were registered before the scope, which are
+    // never reused, unlike the addresses of the metrics
+    baseline: HashSet<u64>,
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl RegistryScope {
+    /// Begin a new scope, blocking until any other scope has been dropped.
+    pub fn new() -> Self {
+        let guard = SCOPE_LOCK.lock();
+        let baseline = crate::metrics()
+            .iter()
+            .filter_map(|entry| entry.id())
+            .collect();
+        Self {
+            baseline,
+            _guard: guard,
+        }
+    }
+
+    /// Iterate over the metrics which were registered after this scope was
+    /// created and are still registered.
+    pub fn metrics<'a>(&'a self, metrics: &'a Metrics) -> impl Iterator<Item = &'a MetricEntry> {
+        metrics
+            .iter()
+            .filter(move |entry| !entry.id().is_some_and(|id| self.baseline.contains(&id)))
+    }
+}
+
+impl Default for RegistryScope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
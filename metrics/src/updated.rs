@@ -0,0 +1,34 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use rustcommon_time::{Nanoseconds, UnixInstant};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Tracks the time a metric was last written, as nanoseconds since the unix
+/// epoch. Zero indicates that the metric has never been written.
+#[derive(Default, Debug)]
+pub(crate) struct LastUpdated(AtomicU64);
+
+impl LastUpdated {
+    pub const fn new() -> Self {
+        Self(AtomicU64::new(0))
+    }
+
+    /// Record that the metric was written. This uses the cached clock so that
+    /// it only costs a load and a store on the write path.
+    #[inline]
+    pub fn touch(&self) {
+        let now = UnixInstant::<Nanoseconds<u64>>::recent();
+        let nanos = (now - UnixInstant::<Nanoseconds<u64>>::from_nanos(0)).as_nanos();
+        // never store zero, which would look like the metric was not written
+        self.0.store(nanos.max(1), Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> Option<UnixInstant<Nanoseconds<u64>>> {
+        match self.0.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(UnixInstant::<Nanoseconds<u64>>::from_nanos(nanos)),
+        }
+    }
+}
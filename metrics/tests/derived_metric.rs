@@ -0,0 +1,59 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use rustcommon_metrics::*;
+
+#[metric(name = "derived/hit")]
+static HIT: Counter = Counter::new();
+
+#[metric(name = "derived/miss")]
+static MISS: Counter = Counter::new();
+
+#[metric(name = "derived/ratio")]
+static RATIO: DerivedMetric = DerivedMetric::new(|| {
+    let hit = HIT.value() as f64;
+    let total = hit + MISS.value() as f64;
+    if total == 0.0 {
+        0.0
+    } else {
+        hit / total
+    }
+});
+
+#[test]
+fn derived_metric() {
+    assert_eq!(RATIO.value(), 0.0);
+
+    HIT.add(1);
+    MISS.add(3);
+    assert_eq!(RATIO.value(), 0.25);
+
+    let metrics = metrics();
+    let entry = metrics
+        .iter()
+        .find(|entry| entry.name() == "derived/ratio")
+        .expect("derived metric not registered");
+    let derived = entry
+        .as_any()
+        .and_then(|any| any.downcast_ref::<DerivedMetric>())
+        .expect("not a derived metric");
+
+    HIT.add(4);
+    assert_eq!(derived.value(), 0.625);
+}
+
+#[test]
+fn dynamic_derived_metric() {
+    let base = 10.0;
+    let metric: DynBoxedMetric<DerivedMetric<Box<dyn Fn() -> f64 + Send + Sync>>> =
+        DynBoxedMetric::new(
+            DerivedMetric::new(Box::new(move || base * 2.0)),
+            "derived/dynamic",
+        );
+
+    assert_eq!(metric.value(), 20.0);
+    assert!(metrics()
+        .iter()
+        .any(|entry| entry.name() == "derived/dynamic"));
+}
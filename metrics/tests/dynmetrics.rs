@@ -121,3 +121,128 @@ fn multi_metric() {
     drop(m2);
     assert_eq!(metrics().dynamic_metrics().len(), 0);
 }
+
+#[test]
+fn builder_metadata() {
+    let _guard = TestGuard::new();
+
+    let boxed = DynMetricBuilder::new("boxed")
+        .namespace("dynamic")
+        .description("a boxed counter")
+        .build(Counter::new());
+
+    let pinned = DynPinnedMetric::new(Gauge::new());
+    let pinned = unsafe { Pin::new_unchecked(&pinned) };
+    DynMetricBuilder::new(format!("pinned.{}", 1))
+        .description("a pinned gauge")
+        .register(pinned);
+
+    {
+        let metrics = metrics();
+        let entries = metrics.dynamic_metrics();
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(entries[0].name(), "boxed");
+        assert_eq!(entries[0].namespace(), Some("dynamic"));
+        assert_eq!(entries[0].description(), Some("a boxed counter"));
+
+        assert_eq!(entries[1].name(), "pinned.1");
+        assert_eq!(entries[1].namespace(), None);
+        assert_eq!(entries[1].description(), Some("a pinned gauge"));
+    }
+
+    drop(boxed);
+    assert_eq!(metrics().dynamic_metrics().len(), 1);
+}
+
+#[metric(name = "static_metric")]
+static STATIC_METRIC: Counter = Counter::new();
+
+#[test]
+fn iter_static_dynamic() {
+    let _guard = TestGuard::new();
+
+    let _dynamic = DynBoxedMetric::new(Counter::new(), "dynamic_metric");
+
+    let metrics = metrics();
+    let names = |iter: std::slice::Iter<'_, MetricEntry>| -> Vec<String> {
+        iter.map(|entry| entry.name().to_owned()).collect()
+    };
+
+    assert_eq!(names(metrics.iter_static()), ["static_metric"]);
+    assert_eq!(names(metrics.iter_dynamic()), ["dynamic_metric"]);
+    assert_eq!(metrics.iter().count(), 2);
+}
+
+#[test]
+fn scoped_metric() {
+    let _guard = TestGuard::new();
+
+    {
+        let metric = ScopedMetric::new(Counter::new(), "scoped");
+        metric.increment();
+        assert_eq!(metrics().dynamic_metrics().len(), 1);
+    }
+    assert_eq!(metrics().dynamic_metrics().len(), 0);
+
+    // dropping a scoped metric while the registry is held does not deadlock
+    let metric = ScopedMetric::new(Counter::new(), "scoped");
+    {
+        let metrics = metrics();
+        drop(metric);
+        assert_eq!(metrics.dynamic_metrics()[0].name(), "scoped");
+    }
+
+    // the metric is unregistered by the next change to the registry
+    let other = DynBoxedMetric::new(Counter::new(), "other");
+    assert_eq!(metrics().dynamic_metrics().len(), 1);
+    drop(other);
+    assert_eq!(metrics().dynamic_metrics().len(), 0);
+}
+
+#[test]
+fn scoped_metric_panic() {
+    let _guard = TestGuard::new();
+
+    let result = std::panic::catch_unwind(|| {
+        let _metric = ScopedMetric::new(Counter::new(), "scoped_panic");
+        assert_eq!(metrics().dynamic_metrics().len(), 1);
+        panic!("task failed");
+    });
+    assert!(result.is_err());
+    assert_eq!(metrics().dynamic_metrics().len(), 0);
+}
+
+#[test]
+fn register_metric_handle() {
+    let _guard = TestGuard::new();
+
+    static SHARED: Counter = Counter::new();
+
+    // each handle only unregisters its own entry for a shared metric
+    let a = register_metric(MetricEntry::new(&SHARED, "handle_a".into()));
+    let b = register_metric(MetricEntry::new(&SHARED, "handle_b".into()));
+    assert_ne!(a.id(), b.id());
+    assert_eq!(metrics().dynamic_metrics().len(), 2);
+
+    drop(a);
+    let names: Vec<String> = metrics()
+        .dynamic_metrics()
+        .iter()
+        .map(|entry| entry.name().to_string())
+        .collect();
+    assert_eq!(names, ["handle_b"]);
+    assert_eq!(
+        metrics()
+            .get_by_id(b.id())
+            .map(|entry| entry.name().to_string()),
+        Some("handle_b".to_string())
+    );
+
+    drop(b);
+    assert_eq!(metrics().dynamic_metrics().len(), 0);
+
+    // a leaked handle leaves the metric registered
+    register_metric(MetricEntry::new(&SHARED, "handle_leaked".into())).leak();
+    assert_eq!(metrics().dynamic_metrics().len(), 1);
+}
@@ -2,6 +2,7 @@
 // Licensed under the Apache License, Version 2.0
 // http://www.apache.org/licenses/LICENSE-2.0
 
+use heatmap::Instant;
 use rustcommon_metrics::{heatmap, metrics};
 
 heatmap!(LATENCY, 1_000_000_000);
@@ -22,3 +23,13 @@ fn metric_name_and_description_as_expected() {
     assert_eq!(metrics[0].name(), "cardinality");
     assert_eq!(metrics[0].description(), Some("some description"));
 }
+
+#[test]
+fn reset_clears_heatmap() {
+    LATENCY.increment(Instant::now(), 100, 1).unwrap();
+    assert!(LATENCY.percentile(100.0).is_ok());
+
+    assert_eq!(metrics().reset("latency"), 1);
+    assert!(LATENCY.percentile(100.0).is_err());
+    assert_eq!(metrics().reset("missing"), 0);
+}
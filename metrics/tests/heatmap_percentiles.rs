@@ -0,0 +1,67 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use rustcommon_metrics::*;
+use rustcommon_time::{Duration, Nanoseconds};
+
+#[metric(name = "cached")]
+static CACHED: Relaxed<Heatmap> = Relaxed::new(|| {
+    Heatmap::builder()
+        .maximum_value(1024)
+        .min_resolution(1)
+        .min_resolution_range(1024)
+        .build()
+        .expect("bad heatmap configuration")
+});
+
+#[metric(name = "not_a_heatmap")]
+static COUNTER: Counter = Counter::new();
+
+fn percentiles(name: &str) -> Option<Vec<(f64, u64)>> {
+    let metrics = metrics();
+    let entry = metrics
+        .iter()
+        .find(|entry| entry.name() == name)
+        .expect("metric not found");
+    metrics.heatmap_percentiles(entry, &[50.0, 100.0])
+}
+
+#[test]
+fn cached_percentiles() {
+    set_percentile_ttl(Duration::<Nanoseconds<u64>>::from_secs(3600));
+
+    assert_eq!(percentiles("not_a_heatmap"), None);
+    COUNTER.increment();
+
+    CACHED.increment(heatmap::Instant::now(), 11, 1).unwrap();
+    assert_eq!(percentiles("cached"), Some(vec![(50.0, 11), (100.0, 11)]));
+
+    // the cached percentiles are reused until the ttl expires
+    CACHED.increment(heatmap::Instant::now(), 101, 3).unwrap();
+    assert_eq!(percentiles("cached"), Some(vec![(50.0, 11), (100.0, 11)]));
+
+    // a heatmap registered after another is dropped is not served the old
+    // heatmap's percentiles, even if it reuses its allocation
+    for value in [21, 43, 63, 85] {
+        let heatmap = Heatmap::builder()
+            .maximum_value(1024)
+            .min_resolution(1)
+            .min_resolution_range(1024)
+            .build()
+            .unwrap();
+        heatmap
+            .increment(heatmap::Instant::now(), value, 1)
+            .unwrap();
+        let heatmap = DynBoxedMetric::new(heatmap, "dynamic");
+        assert_eq!(
+            percentiles("dynamic"),
+            Some(vec![(50.0, value), (100.0, value)])
+        );
+        drop(heatmap);
+    }
+
+    // disabling the cache calculates the percentiles on each call
+    set_percentile_ttl(Duration::<Nanoseconds<u64>>::from_nanos(0));
+    assert_eq!(percentiles("cached"), Some(vec![(50.0, 101), (100.0, 101)]));
+}
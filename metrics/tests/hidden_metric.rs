@@ -0,0 +1,27 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use rustcommon_metrics::*;
+
+#[metric(name = "requests")]
+static REQUESTS: Counter = Counter::new();
+
+#[metric(name = "legacy/requests", hidden = true)]
+static LEGACY_REQUESTS: Counter = Counter::new();
+
+#[test]
+fn hidden() {
+    let metrics = metrics();
+    assert_eq!(metrics.iter().count(), 2);
+
+    let hidden: Vec<_> = metrics
+        .iter()
+        .filter(|entry| entry.is_hidden())
+        .map(|entry| entry.name())
+        .collect();
+    assert_eq!(hidden, vec!["legacy/requests"]);
+
+    let visible: Vec<_> = metrics.visible().map(|entry| entry.name()).collect();
+    assert_eq!(visible, vec!["requests"]);
+}
@@ -0,0 +1,52 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+#![cfg(feature = "last-updated")]
+
+use rustcommon_metrics::*;
+use rustcommon_time::{refresh_clock, Nanoseconds, UnixInstant};
+
+#[metric(name = "updated/counter")]
+static COUNTER: Counter = Counter::new();
+
+#[metric(name = "updated/gauge")]
+static GAUGE: Gauge = Gauge::new();
+
+#[metric(name = "updated/heatmap")]
+static HEATMAP: Relaxed<Heatmap> = Relaxed::new(|| Heatmap::builder().build().unwrap());
+
+fn updated(name: &str) -> Option<UnixInstant<Nanoseconds<u64>>> {
+    metrics()
+        .iter()
+        .find(|entry| entry.name() == name)
+        .expect("metric not found")
+        .last_updated()
+}
+
+#[test]
+fn last_updated() {
+    assert_eq!(updated("updated/counter"), None);
+    assert_eq!(updated("updated/gauge"), None);
+
+    refresh_clock();
+    let before = UnixInstant::<Nanoseconds<u64>>::recent();
+
+    COUNTER.increment();
+    GAUGE.set(5);
+    assert_eq!(COUNTER.last_updated(), Some(before));
+    assert_eq!(updated("updated/counter"), Some(before));
+    assert_eq!(updated("updated/gauge"), Some(before));
+
+    // reading a metric does not update it
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    refresh_clock();
+    let _ = COUNTER.value();
+    assert_eq!(updated("updated/counter"), Some(before));
+
+    GAUGE.decrement();
+    assert!(updated("updated/gauge").unwrap() > before);
+
+    // only counters and gauges track updates
+    assert_eq!(updated("updated/heatmap"), None);
+}
@@ -0,0 +1,30 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use rustcommon_metrics::*;
+
+#[metric(name = "requests", meta(aggregation = "sum", team = "search"))]
+static REQUESTS: Counter = Counter::new();
+
+#[metric(name = "connections")]
+static CONNECTIONS: Gauge = Gauge::new();
+
+#[test]
+fn metadata() {
+    let metrics = metrics();
+    let mut entries: Vec<_> = metrics.iter().collect();
+    entries.sort_by_key(|entry| entry.name());
+    assert_eq!(entries.len(), 2);
+
+    assert_eq!(entries[0].name(), "connections");
+    assert!(entries[0].metadata().is_empty());
+
+    assert_eq!(entries[1].name(), "requests");
+    assert_eq!(
+        entries[1].metadata(),
+        &[("aggregation", "sum"), ("team", "search")]
+    );
+    assert_eq!(entries[1].metadata_value("team"), Some("search"));
+    assert_eq!(entries[1].metadata_value("unit"), None);
+}
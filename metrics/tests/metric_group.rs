@@ -0,0 +1,52 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use rustcommon_metrics::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+static CACHE: MetricGroup = MetricGroup::new();
+
+#[metric(name = "cache/hit")]
+static HIT: Counter = Counter::new();
+
+#[metric(name = "cache/miss")]
+static MISS: Counter = Counter::new();
+
+#[metric(name = "cache/total")]
+static TOTAL: Counter = Counter::new();
+
+#[test]
+fn consistent_reads() {
+    let running = Arc::new(AtomicBool::new(true));
+
+    let writers: Vec<_> = (0..4)
+        .map(|id| {
+            let running = running.clone();
+            std::thread::spawn(move || {
+                while running.load(Ordering::Relaxed) {
+                    CACHE.update(|| {
+                        if id % 2 == 0 {
+                            HIT.increment();
+                        } else {
+                            MISS.increment();
+                        }
+                        TOTAL.increment();
+                    });
+                }
+            })
+        })
+        .collect();
+
+    for _ in 0..100_000 {
+        let (hit, miss, total) =
+            metrics().snapshot_consistent(&CACHE, || (HIT.value(), MISS.value(), TOTAL.value()));
+        assert_eq!(hit + miss, total);
+    }
+
+    running.store(false, Ordering::Relaxed);
+    for writer in writers {
+        writer.join().unwrap();
+    }
+}
@@ -0,0 +1,25 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use rustcommon_metrics::metric_group;
+
+metric_group!("server", [REQUEST, RESPONSE, ERROR,]);
+
+#[test]
+fn metrics_are_present() {
+    let metrics = rustcommon_metrics::metrics();
+    let metrics = metrics.static_metrics();
+
+    assert_eq!(metrics.len(), 3);
+    assert!(metrics
+        .iter()
+        .any(|metric| metric.name() == "server.request"));
+    assert!(metrics
+        .iter()
+        .any(|metric| metric.name() == "server.response"));
+    assert!(metrics.iter().any(|metric| metric.name() == "server.error"));
+
+    REQUEST.increment();
+    assert_eq!(REQUEST.value(), 1);
+}
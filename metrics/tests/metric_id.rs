@@ -0,0 +1,61 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use rustcommon_metrics::*;
+
+#[metric(name = "static.a")]
+static A: Counter = Counter::new();
+
+#[metric(name = "static.b")]
+static B: Counter = Counter::new();
+
+#[test]
+fn ids() {
+    let first = DynBoxedMetric::new(Counter::new(), "dynamic.first");
+    let second = DynBoxedMetric::new(Counter::new(), "dynamic.second");
+
+    let ids: Vec<(u64, String)> = metrics()
+        .iter()
+        .map(|entry| (entry.id().unwrap(), entry.name().to_string()))
+        .collect();
+    assert_eq!(ids.len(), 4);
+
+    // every metric can be found again by its id
+    {
+        let metrics = metrics();
+        for (id, name) in ids.iter() {
+            assert_eq!(
+                metrics.get_by_id(*id).map(|e| e.name()),
+                Some(name.as_str())
+            );
+        }
+        let mut unique: Vec<_> = ids.iter().map(|(id, _)| *id).collect();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), 4);
+    }
+
+    // ids of the remaining metrics are unchanged after unregistering
+    let first_id = ids.iter().find(|(_, n)| n == "dynamic.first").unwrap().0;
+    let second_id = ids.iter().find(|(_, n)| n == "dynamic.second").unwrap().0;
+    drop(first);
+    let metrics = metrics();
+    assert!(metrics.get_by_id(first_id).is_none());
+    assert_eq!(
+        metrics.get_by_id(second_id).map(|e| e.name()),
+        Some("dynamic.second")
+    );
+    drop(metrics);
+    drop(second);
+}
+
+#[test]
+fn unregistered() {
+    static C: Counter = Counter::new();
+
+    // an entry which was never registered has no id, rather than the id of
+    // some other metric
+    let entry = MetricEntry::new(&C, "unregistered".into());
+    assert_eq!(entry.id(), None);
+}
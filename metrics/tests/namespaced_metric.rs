@@ -16,6 +16,7 @@ fn without_namespace() {
     assert_eq!(metrics.len(), 2);
     assert_eq!(metrics[1].name(), "pid");
     assert_eq!(metrics[1].namespace(), None);
+    assert_eq!(metrics[1].full_name("."), "pid");
 }
 
 #[test]
@@ -24,4 +25,6 @@ fn with_namespace() {
     assert_eq!(metrics.len(), 2);
     assert_eq!(metrics[0].name(), "composed/response");
     assert_eq!(metrics[0].namespace(), Some("server"));
+    assert_eq!(metrics[0].full_name("."), "server.composed/response");
+    assert_eq!(metrics[0].full_name("_"), "server_composed/response");
 }
@@ -0,0 +1,28 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use rustcommon_metrics::*;
+use std::sync::atomic::Ordering;
+
+#[test]
+fn counter_ordered() {
+    let counter = Counter::new();
+    assert_eq!(counter.increment_ordered(Ordering::SeqCst), 0);
+    assert_eq!(counter.add_ordered(2, Ordering::Release), 1);
+    assert_eq!(counter.value_ordered(Ordering::Acquire), 3);
+    assert_eq!(counter.set_ordered(7, Ordering::SeqCst), 3);
+    assert_eq!(counter.value(), 7);
+}
+
+#[test]
+fn gauge_ordered() {
+    let gauge = Gauge::new();
+    assert_eq!(gauge.increment_ordered(Ordering::SeqCst), 0);
+    assert_eq!(gauge.add_ordered(3, Ordering::Release), 1);
+    assert_eq!(gauge.sub_ordered(5, Ordering::AcqRel), 4);
+    assert_eq!(gauge.decrement_ordered(Ordering::SeqCst), -1);
+    assert_eq!(gauge.value_ordered(Ordering::Acquire), -2);
+    assert_eq!(gauge.set_ordered(7, Ordering::SeqCst), -2);
+    assert_eq!(gauge.value(), 7);
+}
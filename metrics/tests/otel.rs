@@ -0,0 +1,141 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+#![cfg(feature = "otel")]
+
+use rustcommon_metrics::otel::{AggregationTemporality, Data, Metric, OtlpExporter, Value};
+use rustcommon_metrics::{metric, metrics, Counter, Gauge, Heatmap, Relaxed};
+
+#[metric(name = "requests", description = "total requests")]
+static REQUESTS: Counter = Counter::new();
+
+#[metric(name = "requests.large")]
+static REQUESTS_LARGE: Counter = Counter::new();
+
+#[metric(name = "connections")]
+static CONNECTIONS: Gauge = Gauge::new();
+
+#[metric(name = "memory")]
+static MEMORY: Gauge = Gauge::with_baseline(1024);
+
+#[metric(name = "latency")]
+static LATENCY: Relaxed<Heatmap> = Relaxed::new(|| {
+    Heatmap::builder()
+        .maximum_value(1024)
+        .min_resolution(1)
+        .min_resolution_range(16)
+        .span(heatmap::Duration::from_secs(1))
+        .resolution(heatmap::Duration::from_millis(10))
+        .build()
+        .expect("bad heatmap configuration")
+});
+
+fn get(metrics: &[Metric], name: &str) -> Metric {
+    metrics
+        .iter()
+        .find(|metric| metric.name == name)
+        .cloned()
+        .expect("metric not found")
+}
+
+#[test]
+fn to_otlp() {
+    REQUESTS.add(3);
+    REQUESTS_LARGE.add(u64::MAX - 1);
+    CONNECTIONS.set(-2);
+    MEMORY.set(1536);
+    LATENCY.increment(heatmap::Instant::now(), 5, 2).unwrap();
+    LATENCY.increment(heatmap::Instant::now(), 100, 1).unwrap();
+    // only completed windows of the heatmap are reported
+    std::thread::sleep(std::time::Duration::from_millis(50));
+
+    let mut exporter = OtlpExporter::new();
+    let metrics = exporter.export(&metrics());
+    assert_eq!(metrics.len(), 5);
+
+    let requests = get(&metrics, "requests");
+    assert_eq!(requests.description, "total requests");
+    match requests.data {
+        Data::Sum(sum) => {
+            assert!(sum.is_monotonic);
+            assert_eq!(
+                sum.aggregation_temporality,
+                AggregationTemporality::Cumulative
+            );
+            assert_eq!(sum.data_points[0].value, Value::AsInt(3));
+        }
+        other => panic!("unexpected data: {:?}", other),
+    }
+
+    // counters above the range of the OTLP value saturate
+    match get(&metrics, "requests.large").data {
+        Data::Sum(sum) => assert_eq!(sum.data_points[0].value, Value::AsInt(i64::MAX)),
+        other => panic!("unexpected data: {:?}", other),
+    }
+
+    match get(&metrics, "connections").data {
+        Data::Gauge(gauge) => assert_eq!(gauge.data_points[0].value, Value::AsInt(-2)),
+        other => panic!("unexpected data: {:?}", other),
+    }
+
+    // gauges are reported relative to their baseline
+    assert_eq!(MEMORY.value(), 1536);
+    match get(&metrics, "memory").data {
+        Data::Gauge(gauge) => assert_eq!(gauge.data_points[0].value, Value::AsInt(512)),
+        other => panic!("unexpected data: {:?}", other),
+    }
+
+    match get(&metrics, "latency").data {
+        Data::Histogram(histogram) => {
+            assert_eq!(
+                histogram.aggregation_temporality,
+                AggregationTemporality::Delta
+            );
+            let points = &histogram.data_points;
+            assert!(!points.is_empty());
+            assert_eq!(points.iter().map(|p| p.count).sum::<u64>(), 3);
+            for pair in points.windows(2) {
+                assert!(pair[0].time_unix_nano <= pair[1].start_time_unix_nano);
+            }
+
+            // values are counted in the first bucket whose bound is not less
+            // than the value
+            let count = |value: f64| -> u64 {
+                points
+                    .iter()
+                    .map(|point| {
+                        assert_eq!(point.bucket_counts.len(), point.explicit_bounds.len() + 1);
+                        assert!(point.start_time_unix_nano < point.time_unix_nano);
+                        let index = point
+                            .explicit_bounds
+                            .iter()
+                            .position(|b| *b >= value)
+                            .unwrap();
+                        point.bucket_counts[index]
+                    })
+                    .sum()
+            };
+            assert_eq!(count(5.0), 2);
+            assert_eq!(count(100.0), 1);
+        }
+        other => panic!("unexpected data: {:?}", other),
+    }
+
+    // windows are only reported once by each exporter, so the deltas are not
+    // double counted
+    match get(&exporter.export(&rustcommon_metrics::metrics()), "latency").data {
+        Data::Histogram(histogram) => assert!(histogram.data_points.is_empty()),
+        other => panic!("unexpected data: {:?}", other),
+    }
+
+    // a separate exporter reports the windows independently
+    let mut other = OtlpExporter::new();
+    match get(&other.export(&rustcommon_metrics::metrics()), "latency").data {
+        Data::Histogram(histogram) => {
+            let count: u64 = histogram.data_points.iter().map(|p| p.count).sum();
+            assert_eq!(count, 3);
+        }
+        other => panic!("unexpected data: {:?}", other),
+    }
+}
@@ -0,0 +1,58 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+#![cfg(feature = "test-util")]
+
+use rustcommon_metrics::testing::RegistryScope;
+use rustcommon_metrics::*;
+
+#[metric(name = "scope.static")]
+static STATIC: Counter = Counter::new();
+
+#[test]
+fn scoped_metrics() {
+    let _before = DynBoxedMetric::new(Counter::new(), "scope.before");
+
+    let scope = RegistryScope::new();
+    assert_eq!(scope.metrics(&metrics()).count(), 0);
+
+    let a = DynBoxedMetric::new(Counter::new(), "scope.a");
+    let _b = DynBoxedMetric::new(Gauge::new(), "scope.b");
+
+    {
+        let metrics = metrics();
+        let mut names: Vec<_> = scope.metrics(&metrics).map(|entry| entry.name()).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["scope.a", "scope.b"]);
+    }
+
+    drop(a);
+    let metrics = metrics();
+    let names: Vec<_> = scope.metrics(&metrics).map(|entry| entry.name()).collect();
+    assert_eq!(names, ["scope.b"]);
+}
+
+#[test]
+fn static_metrics_excluded() {
+    let scope = RegistryScope::new();
+    let metrics = metrics();
+    assert!(metrics.iter().any(|entry| entry.name() == "scope.static"));
+    assert!(!scope
+        .metrics(&metrics)
+        .any(|entry| entry.name() == "scope.static"));
+}
+
+#[test]
+fn replaced_metric_included() {
+    let before = DynBoxedMetric::new(Counter::new(), "scope.replaced");
+
+    let scope = RegistryScope::new();
+    // the new metric may reuse the allocation of the one which was dropped
+    drop(before);
+    let _after = DynBoxedMetric::new(Counter::new(), "scope.replacement");
+
+    let metrics = metrics();
+    let names: Vec<_> = scope.metrics(&metrics).map(|entry| entry.name()).collect();
+    assert_eq!(names, ["scope.replacement"]);
+}
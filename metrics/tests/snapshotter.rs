@@ -0,0 +1,44 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use rustcommon_metrics::*;
+use rustcommon_time::{Duration, Instant, Nanoseconds};
+
+fn heatmap() -> Heatmap {
+    Heatmap::builder()
+        .maximum_value(1 << 20)
+        .min_resolution(1)
+        .min_resolution_range(1024)
+        .build()
+        .expect("bad heatmap configuration")
+}
+
+#[test]
+fn snapshotter() {
+    let counter = Counter::new();
+    let heatmap = heatmap();
+
+    let mut snapshotter = Snapshotter::new();
+    assert!(snapshotter.is_empty());
+    snapshotter.add(&counter, &heatmap);
+    assert_eq!(snapshotter.len(), 1);
+
+    // the first tick has nothing to compare against
+    let start = Instant::<Nanoseconds<u64>>::now();
+    counter.add(1000);
+    snapshotter.tick(start);
+    assert!(heatmap.percentile(100.0).is_err());
+
+    // 51 increments over half a second is a rate of 102/s
+    counter.add(51);
+    snapshotter.tick(start + Duration::<Nanoseconds<u64>>::from_nanos(500_000_000));
+    let bucket = heatmap.percentile(100.0).expect("no rate recorded");
+    assert!(bucket.low() <= 102 && 102 <= bucket.high());
+
+    // ticks which do not move forward in time are ignored
+    counter.add(1);
+    snapshotter.tick(start);
+    let bucket = heatmap.percentile(0.0).expect("no rate recorded");
+    assert!(bucket.low() <= 102 && 102 <= bucket.high());
+}
@@ -0,0 +1,45 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use rustcommon_metrics::time::{Duration, Instant, Nanoseconds};
+use rustcommon_metrics::*;
+
+#[metric(name = "requests")]
+static REQUESTS: SummarizedCounter = SummarizedCounter::new(|| {
+    Heatmap::builder()
+        .maximum_value(1_000_000_000)
+        .build()
+        .expect("bad heatmap configuration")
+});
+
+#[test]
+fn rate_recorded_on_tick() {
+    let metrics = metrics().static_metrics();
+    assert_eq!(metrics.len(), 1);
+    assert_eq!(metrics[0].name(), "requests");
+
+    // the first tick establishes the baseline and records no rate
+    let start = Instant::<Nanoseconds<u64>>::now();
+    REQUESTS.tick(start);
+    assert_eq!(REQUESTS.percentile(50.0), Err(heatmap::Error::Empty));
+
+    REQUESTS.add(1000);
+    assert_eq!(REQUESTS.value(), 1000);
+
+    // reading the summary does not record a rate
+    assert_eq!(REQUESTS.percentile(50.0), Err(heatmap::Error::Empty));
+
+    REQUESTS.tick(start + Duration::<Nanoseconds<u64>>::from_secs(1));
+    assert_eq!(
+        REQUESTS.summary().percentile(50.0).map(|b| b.count()),
+        Ok(1)
+    );
+    let rate = REQUESTS.percentile(50.0).unwrap();
+    assert!(rate >= 1000 && rate < 1100);
+
+    // resetting through the registry clears the counter and the summary
+    assert_eq!(rustcommon_metrics::metrics().reset("requests"), 1);
+    assert_eq!(REQUESTS.value(), 0);
+    assert_eq!(REQUESTS.percentile(50.0), Err(heatmap::Error::Empty));
+}
@@ -14,6 +14,23 @@ pub enum StreamstatsError {
     #[error("invalid percentile")]
     /// The requested percentile is not in the range 0.0 - 100.0
     InvalidPercentile,
+    #[error("streamstats is full")]
+    /// The bounded streamstats struct has no capacity for more samples
+    Full,
+}
+
+/// Sorts the values, skipping the sort if they are already in order. This
+/// makes queries cheap for streams which are already monotonic, such as
+/// timestamps. The stable sort is used as it runs in near linear time for
+/// values which are mostly in order, such as slowly changing latencies.
+/// Returns `true` if a full sort was performed.
+fn sort<T: Ord>(values: &mut [T]) -> bool {
+    if values.windows(2).all(|w| w[0] <= w[1]) {
+        false
+    } else {
+        values.sort();
+        true
+    }
 }
 
 /// A datastructure for concurrently writing a stream of values into a buffer
@@ -27,6 +44,7 @@ where
     current: AtomicUsize,
     len: AtomicUsize,
     sorted: RwLock<Vec<<T as Atomic>::Primitive>>,
+    full_sort: AtomicBool,
 }
 
 impl<T> AtomicStreamstats<T>
@@ -47,22 +65,32 @@ where
             current: AtomicUsize::new(0),
             len: AtomicUsize::new(0),
             sorted,
+            full_sort: AtomicBool::new(false),
         }
     }
 
+    /// Returns the maximum number of values which can be held in the buffer.
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
     /// Insert a new value into the buffer.
     pub fn insert(&self, value: <T as Atomic>::Primitive) {
-        let mut current = self.current.load(Ordering::Relaxed);
-        self.buffer[current].store(value, Ordering::Relaxed);
+        let mut current = self.current.load_relaxed();
+        self.buffer[current].store_relaxed(value);
         loop {
             let next = if current < (self.buffer.len() - 1) {
                 current + 1
             } else {
                 0
             };
-            let result =
-                self.current
-                    .compare_exchange(current, next, Ordering::Relaxed, Ordering::Relaxed);
+            // a spurious failure only costs another trip around the loop
+            let result = self.current.compare_exchange_weak(
+                current,
+                next,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            );
             match result {
                 Ok(_) => {
                     break;
@@ -72,14 +100,14 @@ where
                 }
             }
         }
-        if self.len.load(Ordering::Relaxed) < self.buffer.len() {
+        if self.len.load_relaxed() < self.buffer.len() {
             self.len.fetch_add(1, Ordering::Relaxed);
         }
         self.sorted.write().unwrap().clear(); // resort required
     }
 
     fn values(&self) -> usize {
-        let len = self.len.load(Ordering::Relaxed);
+        let len = self.len.load_relaxed();
         if len < self.buffer.len() {
             len
         } else {
@@ -97,6 +125,7 @@ where
         if !(0.0..=100.0).contains(&percentile) {
             return Err(StreamstatsError::InvalidPercentile);
         }
+        self.full_sort.store_relaxed(false);
         let sorted_len = { self.sorted.read().unwrap().len() };
         if sorted_len == 0 {
             let values = self.values();
@@ -104,11 +133,15 @@ where
                 return Err(StreamstatsError::Empty);
             } else {
                 let mut sorted = self.sorted.write().unwrap();
-                let values = self.values();
-                for i in 0..values {
-                    sorted.push(self.buffer[i].load(Ordering::Relaxed));
+                // another query may have sorted the values while waiting for
+                // the lock
+                if sorted.is_empty() {
+                    let values = self.values();
+                    for i in 0..values {
+                        sorted.push(self.buffer[i].load_relaxed());
+                    }
+                    self.full_sort.store_relaxed(sort(&mut sorted));
                 }
-                sorted.sort();
             }
         }
         let sorted = self.sorted.read().unwrap();
@@ -124,10 +157,50 @@ where
         }
     }
 
+    /// Returns `true` if the most recent call to `percentile` had to perform a
+    /// full sort of the samples. Queries which can reuse the sorted samples
+    /// from a previous query, or find the samples already in order, do not.
+    ///
+    /// This is only meaningful when `percentile` is not called concurrently.
+    /// The flag is shared by all callers, so with concurrent queries it may
+    /// report the result of another caller's query.
+    pub fn full_sort(&self) -> bool {
+        self.full_sort.load_relaxed()
+    }
+
+    /// Returns a copy of the samples currently held in the buffer. The samples
+    /// are not returned in any particular order. See `chronological_samples`
+    /// to retrieve them in the order they were inserted.
+    pub fn samples(&self) -> Vec<<T as Atomic>::Primitive> {
+        (0..self.values())
+            .map(|i| self.buffer[i].load_relaxed())
+            .collect()
+    }
+
+    /// Returns a copy of the samples currently held in the buffer in the order
+    /// they were inserted, from the oldest to the newest. The sorted samples
+    /// used for `percentile` are not affected.
+    ///
+    /// The order is only exact when there are no concurrent inserts, as an
+    /// insert which races with the copy may overwrite the oldest sample.
+    pub fn chronological_samples(&self) -> Vec<<T as Atomic>::Primitive> {
+        let values = self.values();
+        // once the buffer is full, the oldest sample is the next to be
+        // overwritten
+        let oldest = if values < self.buffer.len() {
+            0
+        } else {
+            self.current.load_relaxed()
+        };
+        (0..values)
+            .map(|i| self.buffer[(oldest + i) % self.buffer.len()].load_relaxed())
+            .collect()
+    }
+
     /// Clear all samples from the buffer.
-    pub fn clear(&mut self) {
-        self.current.store(0, Ordering::Relaxed);
-        self.len.store(0, Ordering::Relaxed);
+    pub fn clear(&self) {
+        self.current.store_relaxed(0);
+        self.len.store_relaxed(0);
         self.sorted.write().unwrap().clear();
     }
 }
@@ -139,6 +212,7 @@ pub struct Streamstats<T> {
     current: usize,
     oldest: usize,
     sorted: Vec<T>,
+    full_sort: bool,
 }
 
 impl<T> Streamstats<T>
@@ -158,6 +232,7 @@ where
             current: 0,
             oldest: 0,
             sorted,
+            full_sort: false,
         }
     }
 
@@ -192,25 +267,9 @@ where
         if !(0.0..=100.0).contains(&percentile) {
             return Err(StreamstatsError::InvalidPercentile);
         }
-        if self.sorted.is_empty() {
-            let values = self.values();
-            if values == 0 {
-                return Err(StreamstatsError::Empty);
-            } else {
-                if self.current > self.oldest {
-                    for i in self.oldest..self.current {
-                        self.sorted.push(self.buffer[i]);
-                    }
-                } else {
-                    for i in self.oldest..self.buffer.len() {
-                        self.sorted.push(self.buffer[i]);
-                    }
-                    for i in 0..self.current {
-                        self.sorted.push(self.buffer[i]);
-                    }
-                }
-                self.sorted.sort();
-            }
+        self.full_sort = false;
+        if !self.sort_samples() {
+            return Err(StreamstatsError::Empty);
         }
         if percentile == 0.0 {
             Ok(self.sorted[0])
@@ -220,6 +279,49 @@ where
         }
     }
 
+    /// Fills the sorted samples from the buffer unless they are already
+    /// sorted. Returns `false` if there are no samples.
+    fn sort_samples(&mut self) -> bool {
+        if self.sorted.is_empty() {
+            if self.values() == 0 {
+                return false;
+            }
+            if self.current > self.oldest {
+                for i in self.oldest..self.current {
+                    self.sorted.push(self.buffer[i]);
+                }
+            } else {
+                for i in self.oldest..self.buffer.len() {
+                    self.sorted.push(self.buffer[i]);
+                }
+                for i in 0..self.current {
+                    self.sorted.push(self.buffer[i]);
+                }
+            }
+            self.full_sort = sort(&mut self.sorted);
+        }
+        true
+    }
+
+    /// Returns `true` if the most recent call to `percentile` had to perform a
+    /// full sort of the samples. Queries which can reuse the sorted samples
+    /// from a previous query, or find the samples already in order, do not.
+    pub fn full_sort(&self) -> bool {
+        self.full_sort
+    }
+
+    /// Returns an iterator over the samples currently held in the buffer in
+    /// the order they were inserted, from the oldest to the newest. The sorted
+    /// samples used for `percentile` are not affected.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        let (head, tail) = if self.current >= self.oldest {
+            (&self.buffer[self.oldest..self.current], &self.buffer[..0])
+        } else {
+            (&self.buffer[self.oldest..], &self.buffer[..self.current])
+        };
+        head.iter().chain(tail).copied()
+    }
+
     /// Clear all samples from the buffer.
     pub fn clear(&mut self) {
         self.oldest = self.current;
@@ -227,6 +329,139 @@ where
     }
 }
 
+#[cfg(feature = "histogram")]
+impl<T> Streamstats<T>
+where
+    T: Default + Copy + Ord + Into<u64>,
+{
+    /// Returns a `Histogram` built from the provided `Builder` which contains
+    /// the samples currently in the buffer. The buffer is not modified.
+    ///
+    /// This trades the exact values for the compact, fixed size storage of a
+    /// histogram, for example, to retain the distribution of a window once it
+    /// is complete. The samples are sorted, as they are for `percentile`, so
+    /// that each distinct value is recorded once with its count.
+    ///
+    /// An error is returned if the configuration of the `Builder` is invalid
+    /// or if a sample is larger than the maximum value of the histogram.
+    pub fn to_histogram(
+        &mut self,
+        builder: &histogram::Builder,
+    ) -> Result<histogram::Histogram, histogram::Error> {
+        let histogram = builder.clone().build()?;
+        if !self.sort_samples() {
+            return Ok(histogram);
+        }
+
+        let mut start = 0;
+        while start < self.sorted.len() {
+            let value = self.sorted[start];
+            let end = start + self.sorted[start..].partition_point(|v| *v == value);
+            // the buffer can't hold more than `u32::MAX` samples of one value
+            // in practice, but saturate rather than wrap if it does
+            let count = u32::try_from(end - start).unwrap_or(u32::MAX);
+            histogram.increment(value.into(), count)?;
+            start = end;
+        }
+
+        Ok(histogram)
+    }
+}
+
+/// A datastructure for collecting a bounded number of values which can be used
+/// to produce summary statistics such as percentiles. Unlike [`Streamstats`],
+/// which overwrites the oldest value once full, inserting into a full
+/// `BoundedStreamstats` returns an error and the value is not recorded. This
+/// makes it suitable for exact statistics over a capped set of samples.
+pub struct BoundedStreamstats<T> {
+    samples: Vec<T>,
+    capacity: usize,
+    sorted: bool,
+    full_sort: bool,
+}
+
+impl<T> BoundedStreamstats<T>
+where
+    T: Copy + Ord,
+{
+    /// Create a new struct which can hold up to `capacity` values.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: Vec::with_capacity(capacity),
+            capacity,
+            sorted: true,
+            full_sort: false,
+        }
+    }
+
+    /// Returns the maximum number of values which can be held.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of values currently held.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns `true` if no values are held.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Returns `true` if no more values can be inserted.
+    pub fn is_full(&self) -> bool {
+        self.samples.len() >= self.capacity
+    }
+
+    /// Insert a new value. Returns an error if the capacity has been reached,
+    /// in which case the value is not recorded.
+    pub fn insert(&mut self, value: T) -> Result<(), StreamstatsError> {
+        if self.is_full() {
+            return Err(StreamstatsError::Full);
+        }
+        self.samples.push(value);
+        self.sorted = false; // resort required
+        Ok(())
+    }
+
+    /// Return the value closest to the specified percentile. Returns an error
+    /// if there are no values. Percentile must be within the range 0.0 to
+    /// 100.0
+    pub fn percentile(&mut self, percentile: f64) -> Result<T, StreamstatsError> {
+        if !(0.0..=100.0).contains(&percentile) {
+            return Err(StreamstatsError::InvalidPercentile);
+        }
+        self.full_sort = false;
+        if self.samples.is_empty() {
+            return Err(StreamstatsError::Empty);
+        }
+        if !self.sorted {
+            self.full_sort = sort(&mut self.samples);
+            self.sorted = true;
+        }
+        if percentile == 0.0 {
+            Ok(self.samples[0])
+        } else {
+            let need = (percentile / 100.0 * self.samples.len() as f64).ceil() as usize;
+            Ok(self.samples[need - 1])
+        }
+    }
+
+    /// Returns `true` if the most recent call to `percentile` had to perform a
+    /// full sort of the samples. Queries which can reuse the sorted samples
+    /// from a previous query, or find the samples already in order, do not.
+    pub fn full_sort(&self) -> bool {
+        self.full_sort
+    }
+
+    /// Clear all samples, allowing the full capacity to be used again.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.sorted = true;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -245,7 +480,7 @@ mod tests {
             assert_eq!(streamstats.percentile(100.0), Ok(i));
         }
 
-        let mut streamstats = AtomicStreamstats::<AtomicU64>::new(1000);
+        let streamstats = AtomicStreamstats::<AtomicU64>::new(1000);
         assert_eq!(streamstats.percentile(0.0), Err(StreamstatsError::Empty));
         streamstats.insert(1);
         assert_eq!(streamstats.percentile(0.0), Ok(1));
@@ -260,7 +495,7 @@ mod tests {
 
     #[test]
     fn basic_atomic() {
-        let mut streamstats = AtomicStreamstats::<AtomicU64>::new(1000);
+        let streamstats = AtomicStreamstats::<AtomicU64>::new(1000);
         assert_eq!(streamstats.percentile(0.0), Err(StreamstatsError::Empty));
         streamstats.insert(1);
         assert_eq!(streamstats.percentile(0.0), Ok(1));
@@ -272,7 +507,7 @@ mod tests {
             assert_eq!(streamstats.percentile(100.0), Ok(i));
         }
 
-        let mut streamstats = AtomicStreamstats::<AtomicU64>::new(1000);
+        let streamstats = AtomicStreamstats::<AtomicU64>::new(1000);
         assert_eq!(streamstats.percentile(0.0), Err(StreamstatsError::Empty));
         streamstats.insert(1);
         assert_eq!(streamstats.percentile(0.0), Ok(1));
@@ -284,4 +519,135 @@ mod tests {
             assert_eq!(streamstats.percentile(100.0), Ok(i));
         }
     }
+
+    #[test]
+    fn samples() {
+        let streamstats = AtomicStreamstats::<AtomicU64>::new(4);
+        assert!(streamstats.samples().is_empty());
+        for i in 0..6 {
+            streamstats.insert(i);
+        }
+        let mut samples = streamstats.samples();
+        samples.sort_unstable();
+        assert_eq!(samples, vec![2, 3, 4, 5]);
+        streamstats.clear();
+        assert!(streamstats.samples().is_empty());
+    }
+
+    #[test]
+    fn insertion_order() {
+        let mut streamstats = Streamstats::<u64>::new(4);
+        assert_eq!(streamstats.iter().count(), 0);
+        for i in [3, 1, 2] {
+            streamstats.insert(i);
+        }
+        assert_eq!(streamstats.iter().collect::<Vec<_>>(), vec![3, 1, 2]);
+        assert_eq!(streamstats.percentile(0.0), Ok(1));
+
+        // the oldest samples are dropped once the buffer wraps, and the
+        // sorted samples are left in place
+        for i in [5, 4] {
+            streamstats.insert(i);
+        }
+        assert_eq!(streamstats.percentile(100.0), Ok(5));
+        assert!(streamstats.full_sort());
+        assert_eq!(streamstats.iter().collect::<Vec<_>>(), vec![2, 5, 4]);
+        assert_eq!(streamstats.percentile(0.0), Ok(2));
+        assert!(!streamstats.full_sort());
+        streamstats.clear();
+        assert_eq!(streamstats.iter().count(), 0);
+
+        let streamstats = AtomicStreamstats::<AtomicU64>::new(4);
+        assert!(streamstats.chronological_samples().is_empty());
+        for i in [3, 1, 2] {
+            streamstats.insert(i);
+        }
+        assert_eq!(streamstats.chronological_samples(), vec![3, 1, 2]);
+        for i in [5, 4, 0] {
+            streamstats.insert(i);
+        }
+        assert_eq!(streamstats.chronological_samples(), vec![2, 5, 4, 0]);
+        streamstats.clear();
+        assert!(streamstats.chronological_samples().is_empty());
+    }
+
+    #[test]
+    fn bounded() {
+        let mut streamstats = BoundedStreamstats::<u64>::new(100);
+        assert_eq!(streamstats.percentile(0.0), Err(StreamstatsError::Empty));
+
+        for i in (1..=100).rev() {
+            assert_eq!(streamstats.insert(i), Ok(()));
+        }
+        assert!(streamstats.is_full());
+        assert_eq!(streamstats.insert(1000), Err(StreamstatsError::Full));
+        assert_eq!(streamstats.len(), 100);
+
+        assert_eq!(streamstats.percentile(0.0), Ok(1));
+        assert_eq!(streamstats.percentile(50.0), Ok(50));
+        assert_eq!(streamstats.percentile(100.0), Ok(100));
+
+        streamstats.clear();
+        assert!(streamstats.is_empty());
+        assert_eq!(streamstats.insert(7), Ok(()));
+        assert_eq!(streamstats.percentile(100.0), Ok(7));
+    }
+
+    #[cfg(feature = "histogram")]
+    #[test]
+    fn to_histogram() {
+        let builder = histogram::Histogram::builder().maximum_value(1024);
+
+        let mut streamstats = Streamstats::<u64>::new(100);
+        let histogram = streamstats.to_histogram(&builder).unwrap();
+        assert!(histogram.percentile(0.0).is_err());
+
+        for value in [5, 1, 5, 1000, 5, 2] {
+            streamstats.insert(value);
+        }
+        let histogram = streamstats.to_histogram(&builder).unwrap();
+        let counts: Vec<(u64, u32)> = histogram
+            .into_iter()
+            .filter(|bucket| bucket.count() > 0)
+            .map(|bucket| (bucket.high(), bucket.count()))
+            .collect();
+        assert_eq!(counts[..3], [(1, 1), (2, 1), (5, 3)]);
+        assert_eq!(counts.len(), 4);
+        let bucket = histogram.percentile(100.0).unwrap();
+        assert!(bucket.low() <= 1000 && 1000 <= bucket.high());
+
+        // the samples are retained
+        assert_eq!(streamstats.percentile(100.0), Ok(1000));
+
+        streamstats.insert(5000);
+        assert!(streamstats.to_histogram(&builder).is_err());
+    }
+
+    #[test]
+    fn full_sort() {
+        let mut streamstats = Streamstats::<u64>::new(1000);
+        for i in 0..100 {
+            streamstats.insert(i);
+        }
+        assert_eq!(streamstats.percentile(50.0), Ok(49));
+        assert!(!streamstats.full_sort());
+        streamstats.insert(0);
+        assert_eq!(streamstats.percentile(50.0), Ok(49));
+        assert!(streamstats.full_sort());
+        // the sorted samples are reused
+        assert_eq!(streamstats.percentile(100.0), Ok(99));
+        assert!(!streamstats.full_sort());
+
+        let streamstats = AtomicStreamstats::<AtomicU64>::new(1000);
+        for i in 0..100 {
+            streamstats.insert(i);
+        }
+        assert_eq!(streamstats.percentile(50.0), Ok(49));
+        assert!(!streamstats.full_sort());
+        streamstats.insert(0);
+        assert_eq!(streamstats.percentile(50.0), Ok(49));
+        assert!(streamstats.full_sort());
+        assert_eq!(streamstats.percentile(100.0), Ok(99));
+        assert!(!streamstats.full_sort());
+    }
 }
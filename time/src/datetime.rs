@@ -45,33 +45,47 @@ impl DateTime {
     }
 
     pub fn to_rfc3339_opts(&self, seconds_format: SecondsFormat, use_z: bool) -> String {
+        let mut buf = Vec::with_capacity(35);
+        self.write_rfc3339_opts(&mut buf, seconds_format, use_z)
+            .expect("failed to write to buffer");
+        String::from_utf8(buf).expect("rfc3339 timestamps are valid utf8")
+    }
+
+    /// Writes the RFC3339 representation directly to the provided writer. This
+    /// avoids allocating a `String` for each timestamp, for example, when
+    /// writing a timestamp for every log line.
+    pub fn write_rfc3339_opts<W: std::io::Write + ?Sized>(
+        &self,
+        w: &mut W,
+        seconds_format: SecondsFormat,
+        use_z: bool,
+    ) -> Result<(), std::io::Error> {
         let date = self.inner.date();
         let time = self.inner.time();
-        let tz = if use_z { "Z" } else { "+00:00" };
-        let seconds = match seconds_format {
-            SecondsFormat::Secs => {
-                format!("{:02}", time.second())
-            }
+        write!(
+            w,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            date.year(),
+            date.month() as u8,
+            date.day(),
+            time.hour(),
+            time.minute(),
+            time.second(),
+        )?;
+        match seconds_format {
+            SecondsFormat::Secs => {}
             SecondsFormat::Millis => {
-                format!("{:02}.{:03}", time.second(), time.millisecond())
+                write!(w, ".{:03}", time.millisecond())?;
             }
             SecondsFormat::Micros => {
-                format!("{:02}.{:06}", time.second(), time.microsecond())
+                write!(w, ".{:06}", time.microsecond())?;
             }
             SecondsFormat::Nanos => {
-                format!("{:02}.{:09}", time.second(), time.nanosecond())
+                write!(w, ".{:09}", time.nanosecond())?;
             }
-        };
-        format!(
-            "{:04}-{:02}-{:02}T{:02}:{:02}:{}{}",
-            date.year(),
-            date.month() as u8,
-            date.day(),
-            time.hour(),
-            time.minute(),
-            seconds,
-            tz
-        )
+        }
+        let tz: &[u8] = if use_z { b"Z" } else { b"+00:00" };
+        w.write_all(tz)
     }
 }
 
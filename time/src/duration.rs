@@ -4,6 +4,7 @@
 
 use crate::*;
 use core::ops::AddAssign;
+use core::str::FromStr;
 
 #[repr(transparent)]
 pub struct Duration<T> {
@@ -89,8 +90,32 @@ impl Duration<Seconds<u32>> {
     pub fn as_secs(&self) -> u32 {
         self.inner.inner
     }
+
+    pub const fn as_millis(&self) -> u64 {
+        self.inner.inner as u64 * (NANOS_PER_SEC / NANOS_PER_MILLI)
+    }
+
+    pub const fn as_micros(&self) -> u64 {
+        self.inner.inner as u64 * (NANOS_PER_SEC / NANOS_PER_MICRO)
+    }
+
+    /// Returns the whole seconds and the fractional part in nanoseconds, which
+    /// is always zero for this type.
+    pub const fn as_secs_nanos(&self) -> (u64, u32) {
+        (self.inner.inner as u64, 0)
+    }
+
+    pub fn mul_f64(self, rhs: f64) -> Self {
+        Self {
+            inner: Seconds {
+                inner: (self.inner.inner as f64 * rhs) as u32,
+            },
+        }
+    }
 }
 
+duration!(Duration<Seconds<u32>>);
+
 impl core::fmt::Debug for Duration<Seconds<u32>> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("Duration<Seconds<u32>>")
@@ -138,6 +163,18 @@ impl Duration<Nanoseconds<u64>> {
         inner: Nanoseconds { inner: u64::MAX },
     };
 
+    /// Creates a new duration from whole seconds and additional nanoseconds,
+    /// mirroring `std::time::Duration::new`. Nanoseconds beyond one second
+    /// carry over into the seconds.
+    ///
+    /// # Panics
+    /// Panics if the duration cannot be represented with this type.
+    pub fn new(seconds: u64, nanoseconds: u32) -> Self {
+        Self::from_secs(seconds)
+            .checked_add(Self::from_nanos(nanoseconds as u64))
+            .expect("the specified duration could not be represented with this type")
+    }
+
     pub const fn from_nanos(nanoseconds: u64) -> Self {
         Self {
             inner: Nanoseconds { inner: nanoseconds },
@@ -198,6 +235,12 @@ impl Duration<Nanoseconds<u64>> {
         self.inner.inner / NANOS_PER_MICRO
     }
 
+    /// Returns the whole seconds and the fractional part in nanoseconds, for
+    /// example, for formatting.
+    pub const fn as_secs_nanos(&self) -> (u64, u32) {
+        (self.as_secs(), self.subsec_nanos() as u32)
+    }
+
     pub fn mul_f64(self, rhs: f64) -> Self {
         Self {
             inner: Nanoseconds {
@@ -207,6 +250,8 @@ impl Duration<Nanoseconds<u64>> {
     }
 }
 
+duration!(Duration<Nanoseconds<u64>>);
+
 impl core::fmt::Debug for Duration<Nanoseconds<u64>> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("Duration<Nanoseconds<u64>>")
@@ -227,3 +272,81 @@ impl Duration<Nanoseconds<AtomicU64>> {
 
 atomic!(Duration<Nanoseconds<AtomicU64>>, Nanoseconds<u64>);
 atomic_arithmetic!(Duration<Nanoseconds<AtomicU64>>, Duration<Nanoseconds<u64>>);
+
+/// An error which can be returned when parsing a `Duration` from a string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseDurationError {
+    /// The string does not start with an unsigned integer.
+    InvalidNumber,
+    /// The unit is missing or is not supported by the duration type.
+    InvalidUnit,
+    /// The duration is too large to be represented by the duration type.
+    Overflow,
+}
+
+impl core::fmt::Display for ParseDurationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidNumber => write!(f, "invalid number in duration"),
+            Self::InvalidUnit => write!(f, "invalid unit in duration"),
+            Self::Overflow => write!(f, "duration is too large"),
+        }
+    }
+}
+
+impl std::error::Error for ParseDurationError {}
+
+/// Splits a duration string such as `"60s"` into the number and the unit.
+fn split_duration(s: &str) -> Result<(u64, &str), ParseDurationError> {
+    let s = s.trim();
+    let index = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(index);
+    let number = number
+        .parse()
+        .map_err(|_| ParseDurationError::InvalidNumber)?;
+    Ok((number, unit.trim_start()))
+}
+
+/// Parses a duration from an unsigned integer followed by one of the units
+/// `s`, `m`, or `h`, for example, `"60s"` or `"1h"`.
+impl FromStr for Duration<Seconds<u32>> {
+    type Err = ParseDurationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, unit) = split_duration(s)?;
+        let multiplier = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            _ => return Err(ParseDurationError::InvalidUnit),
+        };
+        number
+            .checked_mul(multiplier)
+            .and_then(|secs| u32::try_from(secs).ok())
+            .map(Self::from_secs)
+            .ok_or(ParseDurationError::Overflow)
+    }
+}
+
+/// Parses a duration from an unsigned integer followed by one of the units
+/// `ns`, `us`, `ms`, `s`, `m`, or `h`, for example, `"60s"` or `"250ms"`.
+impl FromStr for Duration<Nanoseconds<u64>> {
+    type Err = ParseDurationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, unit) = split_duration(s)?;
+        let multiplier = match unit {
+            "ns" => 1,
+            "us" => NANOS_PER_MICRO,
+            "ms" => NANOS_PER_MILLI,
+            "s" => NANOS_PER_SEC,
+            "m" => 60 * NANOS_PER_SEC,
+            "h" => 3600 * NANOS_PER_SEC,
+            _ => return Err(ParseDurationError::InvalidUnit),
+        };
+        number
+            .checked_mul(multiplier)
+            .map(Self::from_nanos)
+            .ok_or(ParseDurationError::Overflow)
+    }
+}
@@ -88,6 +88,23 @@ impl Instant<Seconds<u32>> {
         CLOCK.initialize();
         CLOCK.coarse.load(Ordering::Relaxed)
     }
+
+    /// Converts this monotonic instant to wall-clock time by using the pair of
+    /// readings the global clock captured during its most recent refresh.
+    ///
+    /// The result is only as accurate as that pairing. The monotonic clock and
+    /// the realtime clock may drift apart between refreshes (for example, when
+    /// the realtime clock is being slewed or stepped), so the error grows with
+    /// both the time since the last call to `refresh_clock()` and the distance
+    /// between this instant and the time of that refresh.
+    pub fn to_unix(&self) -> UnixInstant<Seconds<u32>> {
+        let (instant, unix) = CLOCK.coarse_pair();
+        if *self >= instant {
+            unix + (*self - instant)
+        } else {
+            unix - (instant - *self)
+        }
+    }
 }
 
 impl core::fmt::Debug for Instant<Seconds<u32>> {
@@ -111,6 +128,7 @@ impl Instant<Seconds<AtomicU32>> {
 }
 
 atomic!(Instant<Seconds<AtomicU32>>, Seconds<u32>);
+atomic_arithmetic!(Instant<Seconds<AtomicU32>>, Duration<Seconds<u32>>);
 
 impl Instant<Nanoseconds<u64>> {
     pub fn now() -> Self {
@@ -131,6 +149,23 @@ impl Instant<Nanoseconds<u64>> {
         CLOCK.initialize();
         CLOCK.precise.load(Ordering::Relaxed)
     }
+
+    /// Converts this monotonic instant to wall-clock time by using the pair of
+    /// readings the global clock captured during its most recent refresh.
+    ///
+    /// The result is only as accurate as that pairing. The monotonic clock and
+    /// the realtime clock may drift apart between refreshes (for example, when
+    /// the realtime clock is being slewed or stepped), so the error grows with
+    /// both the time since the last call to `refresh_clock()` and the distance
+    /// between this instant and the time of that refresh.
+    pub fn to_unix(&self) -> UnixInstant<Nanoseconds<u64>> {
+        let (instant, unix) = CLOCK.precise_pair();
+        if *self >= instant {
+            unix + (*self - instant)
+        } else {
+            unix - (instant - *self)
+        }
+    }
 }
 
 impl core::fmt::Debug for Instant<Nanoseconds<u64>> {
@@ -3,19 +3,21 @@
 // http://www.apache.org/licenses/LICENSE-2.0
 
 use core::sync::atomic::AtomicUsize;
-use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use core::sync::atomic::{fence, AtomicU32, AtomicU64, Ordering};
 
 mod datetime;
 mod duration;
 mod instant;
 #[macro_use]
 mod macros;
+mod stopwatch;
 mod units;
 mod unix;
 
 pub use datetime::*;
 pub use duration::*;
 pub use instant::*;
+pub use stopwatch::*;
 pub use units::*;
 pub use unix::*;
 
@@ -38,6 +40,9 @@ pub fn refresh_clock() {
 // Clock provides functionality to get current and recent times
 struct Clock {
     state: AtomicUsize,
+    // incremented before and after each refresh stores the readings, so it is
+    // odd while a refresh is in progress
+    sequence: AtomicU64,
     coarse: Instant<Seconds<AtomicU32>>,
     precise: Instant<Nanoseconds<AtomicU64>>,
     coarse_unix: UnixInstant<Seconds<AtomicU32>>,
@@ -49,6 +54,7 @@ impl Clock {
         Clock {
             // holds the clock state, start as an uninitialized clock
             state: AtomicUsize::new(UNINITIALIZED),
+            sequence: AtomicU64::new(0),
 
             // store a monotonic clock reading
             coarse: Instant {
@@ -82,8 +88,64 @@ impl Clock {
         }
     }
 
+    /// Returns the monotonic and realtime readings which were captured
+    /// together during the most recent refresh.
+    fn precise_pair(&self) -> (Instant<Nanoseconds<u64>>, UnixInstant<Nanoseconds<u64>>) {
+        self.read(|| {
+            (
+                self.precise.load(Ordering::Relaxed),
+                self.precise_unix.load(Ordering::Relaxed),
+            )
+        })
+    }
+
+    /// Returns the coarse monotonic and realtime readings which were captured
+    /// together during the most recent refresh.
+    fn coarse_pair(&self) -> (Instant<Seconds<u32>>, UnixInstant<Seconds<u32>>) {
+        self.read(|| {
+            (
+                self.coarse.load(Ordering::Relaxed),
+                self.coarse_unix.load(Ordering::Relaxed),
+            )
+        })
+    }
+
+    // Performs the reads in `f`, retrying until they all come from the same
+    // refresh. A refresh which started or completed while reading changes the
+    // sequence, even if the state is the same before and after.
+    fn read<T>(&self, f: impl Fn() -> T) -> T {
+        self.initialize();
+        loop {
+            let sequence = self.sequence.load(Ordering::Acquire);
+            if sequence % 2 == 1 {
+                core::hint::spin_loop();
+                continue;
+            }
+            let value = f();
+            fence(Ordering::Acquire);
+            if self.sequence.load(Ordering::Relaxed) == sequence {
+                return value;
+            }
+        }
+    }
+
+    // Marks the start of a refresh, which must hold the `REFRESHING` state.
+    fn begin_write(&self) {
+        self.sequence.fetch_add(1, Ordering::Relaxed);
+        fence(Ordering::Release);
+    }
+
+    // Marks the end of a refresh.
+    fn end_write(&self) {
+        self.sequence.fetch_add(1, Ordering::Release);
+    }
+
     /// Refresh the cached time
     fn refresh(&self) {
+        // these are single attempts rather than CAS loops, so they must use the
+        // strong `compare_exchange`. a spurious failure during initialization
+        // would leave every caller waiting for an initialization that never
+        // happens.
         match self.state.load(Ordering::Relaxed) {
             UNINITIALIZED => {
                 if self
@@ -96,6 +158,7 @@ impl Clock {
                     )
                     .is_ok()
                 {
+                    self.begin_write();
                     let mut ts = libc::timespec {
                         tv_sec: 0,
                         tv_nsec: 0,
@@ -137,6 +200,7 @@ impl Clock {
                     );
 
                     // finalize initialization
+                    self.end_write();
                     self.state.store(INITIALIZED, Ordering::Release);
                 }
                 // if we raced, we should block until the other thread completes
@@ -154,6 +218,7 @@ impl Clock {
                     )
                     .is_ok()
                 {
+                    self.begin_write();
                     let mut ts = libc::timespec {
                         tv_sec: 0,
                         tv_nsec: 0,
@@ -204,6 +269,7 @@ impl Clock {
                     );
 
                     // finalize refresh
+                    self.end_write();
                     self.state.store(INITIALIZED, Ordering::Relaxed);
                 }
                 // if we raced, we should block until the other thread completes
@@ -243,4 +309,232 @@ mod tests {
         assert!((t1 - t0).as_secs_f64() >= 1.0);
         assert!((t1 - t0).as_secs() >= 1);
     }
+
+    #[test]
+    fn to_unix() {
+        refresh_clock();
+        let instant = Instant::<Nanoseconds<u64>>::now();
+        let unix = UnixInstant::<Nanoseconds<u64>>::now();
+        let converted = instant.to_unix();
+        let error = if converted > unix {
+            converted - unix
+        } else {
+            unix - converted
+        };
+        assert!(error < Duration::<Nanoseconds<u64>>::from_millis(100));
+
+        // other tests may refresh the clock between the two conversions, so
+        // they may use different readings
+        let earlier = instant - Duration::<Nanoseconds<u64>>::from_secs(1);
+        let difference = converted - earlier.to_unix();
+        assert!(difference > Duration::<Nanoseconds<u64>>::from_millis(900));
+        assert!(difference < Duration::<Nanoseconds<u64>>::from_millis(1100));
+    }
+
+    #[test]
+    fn duration_components() {
+        let duration = Duration::<Nanoseconds<u64>>::from_nanos(2_345_678_901);
+        assert_eq!(duration.as_secs_nanos(), (2, 345_678_901));
+        assert_eq!(duration.as_millis(), 2_345);
+        assert_eq!(duration.as_micros(), 2_345_678);
+
+        let duration = Duration::<Seconds<u32>>::from_secs(3);
+        assert_eq!(duration.as_secs_nanos(), (3, 0));
+        assert_eq!(duration.as_millis(), 3_000);
+        assert_eq!(duration.as_micros(), 3_000_000);
+    }
+
+    #[test]
+    fn parse_duration() {
+        assert_eq!(
+            "60s".parse(),
+            Ok(Duration::<Nanoseconds<u64>>::from_secs(60))
+        );
+        assert_eq!(
+            " 250 ms ".parse(),
+            Ok(Duration::<Nanoseconds<u64>>::from_millis(250))
+        );
+        assert_eq!(
+            "2h".parse(),
+            Ok(Duration::<Nanoseconds<u64>>::from_secs(7200))
+        );
+        assert_eq!(
+            "7ns".parse(),
+            Ok(Duration::<Nanoseconds<u64>>::from_nanos(7))
+        );
+        assert_eq!("1m".parse(), Ok(Duration::<Seconds<u32>>::from_secs(60)));
+
+        assert_eq!(
+            "s".parse::<Duration<Nanoseconds<u64>>>(),
+            Err(ParseDurationError::InvalidNumber)
+        );
+        assert_eq!(
+            "-1s".parse::<Duration<Nanoseconds<u64>>>(),
+            Err(ParseDurationError::InvalidNumber)
+        );
+        assert_eq!(
+            "60".parse::<Duration<Nanoseconds<u64>>>(),
+            Err(ParseDurationError::InvalidUnit)
+        );
+        assert_eq!(
+            "60d".parse::<Duration<Nanoseconds<u64>>>(),
+            Err(ParseDurationError::InvalidUnit)
+        );
+        assert_eq!(
+            "500ms".parse::<Duration<Seconds<u32>>>(),
+            Err(ParseDurationError::InvalidUnit)
+        );
+        assert_eq!(
+            "18446744073710s".parse::<Duration<Nanoseconds<u64>>>(),
+            Err(ParseDurationError::Overflow)
+        );
+        assert_eq!(
+            "4294967296s".parse::<Duration<Seconds<u32>>>(),
+            Err(ParseDurationError::Overflow)
+        );
+    }
+
+    #[test]
+    fn duration_mul_div() {
+        let base = Duration::<Nanoseconds<u64>>::from_millis(100);
+        assert_eq!(
+            base.checked_mul(8),
+            Some(Duration::<Nanoseconds<u64>>::from_millis(800))
+        );
+        assert_eq!(
+            base.checked_div(4),
+            Some(Duration::<Nanoseconds<u64>>::from_millis(25))
+        );
+        assert_eq!(base.checked_div(0), None);
+
+        // at the u64 nanosecond boundary
+        let half = Duration::<Nanoseconds<u64>>::from_nanos(u64::MAX / 2);
+        assert_eq!(
+            half.checked_mul(2),
+            Some(Duration::<Nanoseconds<u64>>::from_nanos(u64::MAX - 1))
+        );
+        assert_eq!(half.checked_mul(3), None);
+        assert_eq!(half.saturating_mul(3), Duration::<Nanoseconds<u64>>::MAX);
+        assert_eq!(
+            Duration::<Nanoseconds<u64>>::MAX.checked_mul(1),
+            Some(Duration::<Nanoseconds<u64>>::MAX)
+        );
+        assert_eq!(Duration::<Nanoseconds<u64>>::MAX.checked_mul(2), None);
+
+        let duration = Duration::<Seconds<u32>>::from_secs(u32::MAX / 2 + 1);
+        assert_eq!(duration.checked_mul(2), None);
+        assert_eq!(duration.saturating_mul(2), Duration::<Seconds<u32>>::MAX);
+        assert_eq!(
+            duration.checked_div(2),
+            Some(Duration::<Seconds<u32>>::from_secs(u32::MAX / 4 + 1))
+        );
+    }
+
+    #[test]
+    fn duration_mixed_units() {
+        assert_eq!(
+            Duration::<Nanoseconds<u64>>::new(1, 500_000_000),
+            Duration::<Nanoseconds<u64>>::from_millis(1_500)
+        );
+        assert_eq!(
+            Duration::<Nanoseconds<u64>>::new(1, 2_000_000_000),
+            Duration::<Nanoseconds<u64>>::from_secs(3)
+        );
+        assert_eq!(
+            Duration::<Nanoseconds<u64>>::from_secs(1)
+                + Duration::<Nanoseconds<u64>>::from_millis(500),
+            Duration::<Nanoseconds<u64>>::new(1, 500_000_000)
+        );
+        assert_eq!(
+            Duration::<Nanoseconds<u64>>::from_secs(1)
+                - Duration::<Nanoseconds<u64>>::from_micros(1),
+            Duration::<Nanoseconds<u64>>::from_nanos(999_999_000)
+        );
+        assert_eq!(
+            Duration::<Seconds<u32>>::from_secs(60) + Duration::<Seconds<u32>>::from_secs(30),
+            Duration::<Seconds<u32>>::from_secs(90)
+        );
+
+        // overflow is reported by the checked and saturating variants
+        let max = Duration::<Nanoseconds<u64>>::MAX;
+        let one = Duration::<Nanoseconds<u64>>::NANOSECOND;
+        assert_eq!(max.checked_add(one), None);
+        assert_eq!(max.saturating_add(one), max);
+        assert_eq!(one.checked_sub(max), None);
+        assert_eq!(one.saturating_sub(max), Duration::<Nanoseconds<u64>>::ZERO);
+    }
+
+    #[test]
+    #[should_panic]
+    fn duration_add_overflow() {
+        let _ = Duration::<Nanoseconds<u64>>::MAX + Duration::<Nanoseconds<u64>>::NANOSECOND;
+    }
+
+    #[test]
+    fn round_down() {
+        let instant = UnixInstant::<Nanoseconds<u64>>::from_nanos(1_600_000_061_123_456_789);
+        assert_eq!(
+            instant.round_down(Duration::<Nanoseconds<u64>>::from_secs(60)),
+            UnixInstant::<Nanoseconds<u64>>::from_nanos(1_600_000_020_000_000_000)
+        );
+        assert_eq!(
+            instant.round_down(Duration::<Nanoseconds<u64>>::from_nanos(0)),
+            instant
+        );
+
+        let instant = UnixInstant::<Seconds<u32>>::from_secs(1_600_000_061);
+        assert_eq!(
+            instant.round_down(Duration::<Seconds<u32>>::from_secs(60)),
+            UnixInstant::<Seconds<u32>>::from_secs(1_600_000_020)
+        );
+
+        let instant = Instant::<Nanoseconds<u64>>::now();
+        let rounded = instant.round_down(Duration::<Nanoseconds<u64>>::from_secs(1));
+        assert!(rounded <= instant);
+        assert!(instant - rounded < Duration::<Nanoseconds<u64>>::from_secs(1));
+    }
+
+    #[test]
+    fn stopwatch() {
+        let stopwatch = Stopwatch::start();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert!(stopwatch.stop() >= Duration::<Nanoseconds<u64>>::from_millis(10));
+
+        let mut stopped = None;
+        let stopwatch = Stopwatch::new(|elapsed| stopped = Some(elapsed));
+        let elapsed = stopwatch.stop();
+        assert_eq!(stopped, Some(elapsed));
+
+        // the function is called once when the stopwatch is dropped
+        let mut calls = 0;
+        {
+            let _stopwatch = Stopwatch::new(|_| calls += 1);
+        }
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn rfc3339() {
+        let datetime = DateTime::from(UnixInstant::<Nanoseconds<u64>>::from_nanos(
+            1_600_000_000_123_456_789,
+        ));
+        assert_eq!(
+            datetime.to_rfc3339_opts(SecondsFormat::Secs, true),
+            "2020-09-13T12:26:40Z"
+        );
+        assert_eq!(
+            datetime.to_rfc3339_opts(SecondsFormat::Millis, false),
+            "2020-09-13T12:26:40.123+00:00"
+        );
+        assert_eq!(
+            datetime.to_rfc3339_opts(SecondsFormat::Micros, true),
+            "2020-09-13T12:26:40.123456Z"
+        );
+
+        let mut buf = Vec::new();
+        datetime
+            .write_rfc3339_opts(&mut buf, SecondsFormat::Nanos, false)
+            .unwrap();
+        assert_eq!(buf, b"2020-09-13T12:26:40.123456789+00:00");
+    }
 }
@@ -97,6 +97,24 @@ macro_rules! unit {
                     inner: self.inner.saturating_sub(other.inner),
                 }
             }
+
+            pub fn checked_mul(&self, rhs: u32) -> Option<Self> {
+                Some(Self {
+                    inner: self.inner.checked_mul(<$type>::from(rhs))?,
+                })
+            }
+
+            pub fn saturating_mul(&self, rhs: u32) -> Self {
+                Self {
+                    inner: self.inner.saturating_mul(<$type>::from(rhs)),
+                }
+            }
+
+            pub fn checked_div(&self, rhs: u32) -> Option<Self> {
+                Some(Self {
+                    inner: self.inner.checked_div(<$type>::from(rhs))?,
+                })
+            }
         }
 
         impl core::ops::Add<$name<$type>> for $name<$type> {
@@ -133,6 +151,91 @@ macro_rules! unit {
     };
 }
 
+#[macro_export]
+macro_rules! duration {
+    ($name:ident<$unit:ty>) => {
+        impl $name<$unit> {
+            /// Multiplies the duration by `rhs`, returning `None` if the result
+            /// would overflow.
+            pub fn checked_mul(&self, rhs: u32) -> Option<Self> {
+                Some(Self {
+                    inner: self.inner.checked_mul(rhs)?,
+                })
+            }
+
+            /// Multiplies the duration by `rhs`, clamping the result to the
+            /// maximum representable duration on overflow.
+            pub fn saturating_mul(&self, rhs: u32) -> Self {
+                Self {
+                    inner: self.inner.saturating_mul(rhs),
+                }
+            }
+
+            /// Divides the duration by `rhs`, returning `None` if `rhs` is zero.
+            pub fn checked_div(&self, rhs: u32) -> Option<Self> {
+                Some(Self {
+                    inner: self.inner.checked_div(rhs)?,
+                })
+            }
+
+            /// Adds two durations, returning `None` if the result would
+            /// overflow.
+            pub fn checked_add(&self, other: Self) -> Option<Self> {
+                Some(Self {
+                    inner: self.inner.checked_add(other.inner)?,
+                })
+            }
+
+            /// Subtracts `other` from the duration, returning `None` if the
+            /// result would be negative.
+            pub fn checked_sub(&self, other: Self) -> Option<Self> {
+                Some(Self {
+                    inner: self.inner.checked_sub(other.inner)?,
+                })
+            }
+
+            /// Adds two durations, clamping the result to the maximum
+            /// representable duration on overflow.
+            pub fn saturating_add(&self, other: Self) -> Self {
+                Self {
+                    inner: self.inner.saturating_add(other.inner),
+                }
+            }
+
+            /// Subtracts `other` from the duration, clamping the result to zero.
+            pub fn saturating_sub(&self, other: Self) -> Self {
+                Self {
+                    inner: self.inner.saturating_sub(other.inner),
+                }
+            }
+        }
+
+        impl core::ops::Add<$name<$unit>> for $name<$unit> {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self::Output {
+                self.checked_add(other)
+                    .expect("overflow when adding durations")
+            }
+        }
+
+        impl core::ops::Sub<$name<$unit>> for $name<$unit> {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self::Output {
+                self.checked_sub(other)
+                    .expect("overflow when subtracting durations")
+            }
+        }
+
+        impl core::ops::SubAssign<$name<$unit>> for $name<$unit> {
+            fn sub_assign(&mut self, other: Self) {
+                *self = *self - other;
+            }
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! instant {
     ($name:ident<$unit:ty>) => {
@@ -171,6 +274,24 @@ macro_rules! instant {
                     inner: self.inner.checked_sub(other.inner)?,
                 })
             }
+
+            pub fn saturating_sub(&self, other: Duration<$unit>) -> Self {
+                Self {
+                    inner: self.inner.saturating_sub(other.inner),
+                }
+            }
+
+            /// Rounds this instant down to the nearest multiple of `resolution`
+            /// since the epoch. This can be used to align instants to a grid
+            /// of fixed-size time windows. A zero `resolution` returns the
+            /// instant unchanged.
+            pub fn round_down(&self, resolution: Duration<$unit>) -> Self {
+                let mut inner = self.inner;
+                if resolution.inner.inner != 0 {
+                    inner.inner -= inner.inner % resolution.inner.inner;
+                }
+                Self { inner }
+            }
         }
 
         impl core::ops::Add<Duration<$unit>> for $name<$unit> {
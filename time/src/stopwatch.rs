@@ -0,0 +1,86 @@
+// Copyright 2022 Twitter, Inc.
+// Licensed under the Apache License, Version 2.0
+// http://www.apache.org/licenses/LICENSE-2.0
+
+use crate::*;
+
+/// Measures the time elapsed since it was created, for example, to time a
+/// block of code.
+///
+/// The provided function is called with the elapsed time when the stopwatch is
+/// stopped, or when it is dropped if it was not stopped explicitly. This makes
+/// it easy to record the duration of a scope, including any early returns.
+///
+/// # Example
+/// ```
+/// # use rustcommon_time::*;
+/// # let mut recorded = None;
+/// {
+///     let _timer = Stopwatch::new(|elapsed| recorded = Some(elapsed));
+///     // the code to time
+/// }
+/// assert!(recorded.is_some());
+/// ```
+pub struct Stopwatch<F = fn(Duration<Nanoseconds<u64>>)>
+where
+    F: FnOnce(Duration<Nanoseconds<u64>>),
+{
+    start: Instant<Nanoseconds<u64>>,
+    on_stop: Option<F>,
+}
+
+impl Stopwatch {
+    /// Start a stopwatch which does nothing when stopped. The elapsed time
+    /// can be read with `elapsed()` or `stop()`.
+    pub fn start() -> Self {
+        Self::new(|_| {})
+    }
+}
+
+impl<F> Stopwatch<F>
+where
+    F: FnOnce(Duration<Nanoseconds<u64>>),
+{
+    /// Start a stopwatch which calls the provided function with the elapsed
+    /// time when it is stopped or dropped.
+    pub fn new(on_stop: F) -> Self {
+        Self {
+            start: Instant::<Nanoseconds<u64>>::now(),
+            on_stop: Some(on_stop),
+        }
+    }
+
+    /// Returns the instant at which the stopwatch was started.
+    pub fn start_time(&self) -> Instant<Nanoseconds<u64>> {
+        self.start
+    }
+
+    /// Returns the time elapsed since the stopwatch was started without
+    /// stopping it.
+    pub fn elapsed(&self) -> Duration<Nanoseconds<u64>> {
+        self.start.elapsed()
+    }
+
+    /// Stop the stopwatch, calling the function with the elapsed time and
+    /// returning it.
+    pub fn stop(mut self) -> Duration<Nanoseconds<u64>> {
+        self.finish()
+    }
+
+    fn finish(&mut self) -> Duration<Nanoseconds<u64>> {
+        let elapsed = self.elapsed();
+        if let Some(on_stop) = self.on_stop.take() {
+            on_stop(elapsed);
+        }
+        elapsed
+    }
+}
+
+impl<F> Drop for Stopwatch<F>
+where
+    F: FnOnce(Duration<Nanoseconds<u64>>),
+{
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
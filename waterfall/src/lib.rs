@@ -17,6 +17,9 @@ use rusttype::{point, Font, PositionedGlyph, Scale as TypeScale};
 use std::collections::HashMap;
 use std::convert::TryInto;
 
+/// The default font size for labels, in pixels.
+const DEFAULT_FONT_SIZE: f32 = 25.0;
+
 #[derive(Copy, Clone)]
 /// Used to configure various strategies for mapping values to colors
 pub enum Scale {
@@ -33,6 +36,8 @@ pub struct WaterfallBuilder {
     interval: Duration,
     scale: Scale,
     smooth: Option<f32>,
+    value_font_size: f32,
+    time_font_size: f32,
 }
 
 impl WaterfallBuilder {
@@ -44,6 +49,8 @@ impl WaterfallBuilder {
             interval: Duration::from_secs(60),
             scale: Scale::Linear,
             smooth: None,
+            value_font_size: DEFAULT_FONT_SIZE,
+            time_font_size: DEFAULT_FONT_SIZE,
         }
     }
 
@@ -71,6 +78,20 @@ impl WaterfallBuilder {
         self
     }
 
+    /// Sets the font size, in pixels, of the value labels along the horizontal
+    /// axis
+    pub fn value_font_size(mut self, size: f32) -> Self {
+        self.value_font_size = size;
+        self
+    }
+
+    /// Sets the font size, in pixels, of the timestamp labels along the
+    /// vertical axis
+    pub fn time_font_size(mut self, size: f32) -> Self {
+        self.time_font_size = size;
+        self
+    }
+
     // get the scaled weight for a bucket count / width
     fn weight(&self, count: u64, width: u64) -> f64 {
         match self.scale {
@@ -80,11 +101,11 @@ impl WaterfallBuilder {
     }
 
     // find the bucket with the highest weight
-    fn max_weight(&self, heatmap: &heatmap::Heatmap) -> f64 {
+    fn max_weight<U: TimeUnit>(&self, heatmap: &heatmap::Heatmap<U>) -> f64 {
         let mut max_weight = 0.0;
         for slice in heatmap {
             for b in slice.histogram() {
-                let weight = self.weight(b.count().into(), b.high() - b.low() + 1);
+                let weight = self.weight(b.count().into(), b.width());
                 if weight > max_weight {
                     max_weight = weight;
                 }
@@ -93,10 +114,17 @@ impl WaterfallBuilder {
         max_weight
     }
 
-    /// Generate the waterfall from the provided heatmap
-    pub fn build(self, heatmap: &heatmap::Heatmap) {
+    /// Generate the waterfall from the provided heatmap. The timestamp labels
+    /// follow the clock of the heatmap, so they are only as precise as its
+    /// `TimeUnit`.
+    ///
+    /// The heatmap is read several times while rendering. To render a heatmap
+    /// which is still being recorded into, pass a snapshot taken with
+    /// `Heatmap::clone()` so that each pass sees the same counts. Nothing is
+    /// rendered for a heatmap without any windows.
+    pub fn build<U: TimeUnit>(self, heatmap: &heatmap::Heatmap<U>) {
         let now_datetime = DateTime::now();
-        let now_instant = Instant::now();
+        let now_instant = U::now();
 
         let height = heatmap.windows();
         let width = heatmap.buckets();
@@ -104,7 +132,10 @@ impl WaterfallBuilder {
         let mut buf = RgbImage::new(width.try_into().unwrap(), height.try_into().unwrap());
 
         // need to know the start time of the heatmap
-        let begin_instant = heatmap.into_iter().next().unwrap().start();
+        let begin_instant = match heatmap.oldest() {
+            Some(instant) => instant,
+            None => return,
+        };
 
         let max_weight = self.max_weight(heatmap);
 
@@ -129,7 +160,7 @@ impl WaterfallBuilder {
             // build grayscale buffer
             for (y, slice) in heatmap.into_iter().enumerate() {
                 for (x, b) in slice.histogram().into_iter().enumerate() {
-                    let weight = self.weight(b.count().into(), b.high() - b.low() + 1);
+                    let weight = self.weight(b.count().into(), b.width());
                     let scaled_weight = weight / max_weight;
                     let index = (scaled_weight * (colors.len() - 1) as f64).round() as u8;
                     buf.put_pixel(
@@ -155,7 +186,7 @@ impl WaterfallBuilder {
             // set the pixels in the buffer
             for (y, slice) in heatmap.into_iter().enumerate() {
                 for (x, b) in slice.histogram().into_iter().enumerate() {
-                    let weight = self.weight(b.count().into(), b.high() - b.low() + 1);
+                    let weight = self.weight(b.count().into(), b.width());
                     let scaled_weight = weight / max_weight;
                     let index = (scaled_weight * (colors.len() - 1) as f64).round() as usize;
                     let color = colors[index];
@@ -175,7 +206,7 @@ impl WaterfallBuilder {
                 let value = bucket.high();
                 if value >= label_keys[l] {
                     if let Some(label) = labels.get(&label_keys[l]) {
-                        render_text(label, 25.0, x, 0, &mut buf);
+                        render_text(label, self.value_font_size, x, 0, &mut buf);
                         for y in 0..height {
                             buf.put_pixel(
                                 x.try_into().unwrap(),
@@ -192,19 +223,24 @@ impl WaterfallBuilder {
             }
         }
 
-        let offset = std::time::Duration::from_nanos((now_instant - begin_instant).as_nanos() as _);
+        let offset = std::time::Duration::from_nanos(U::as_nanos(U::duration_since(
+            now_instant,
+            begin_instant,
+        )));
 
         let begin_utc = now_datetime - offset;
-        let mut begin = begin_instant;
+        let interval = self.interval.as_nanos();
+        // offset of the most recent timestamp label from the beginning
+        let mut begin = 0;
 
         // add the timestamp labels along the left side
         for (y, slice) in heatmap.into_iter().enumerate() {
-            let slice_start_utc = begin_utc
-                + std::time::Duration::from_nanos((slice.start() - begin_instant).as_nanos() as _);
+            let slice_offset = U::as_nanos(U::duration_since(slice.start(), begin_instant));
+            let slice_start_utc = begin_utc + std::time::Duration::from_nanos(slice_offset);
 
-            if slice.start() - begin >= self.interval {
+            if slice_offset.saturating_sub(begin) >= interval {
                 let label = format!("{}", slice_start_utc);
-                render_text(&label, 25.0, 0, y + 2, &mut buf);
+                render_text(&label, self.time_font_size, 0, y + 2, &mut buf);
                 for x in 0..width {
                     buf.put_pixel(
                         x.try_into().unwrap(),
@@ -212,7 +248,7 @@ impl WaterfallBuilder {
                         Rgb([255, 255, 255]),
                     );
                 }
-                begin += self.interval;
+                begin += interval;
             }
         }
         buf.save(&self.output).unwrap();
@@ -246,13 +282,17 @@ fn render_text(string: &str, size: f32, x_pos: usize, y_pos: usize, buf: &mut Rg
     for g in glyphs {
         if let Some(bb) = g.pixel_bounding_box() {
             g.draw(|x, y, v| {
-                let x = (x as i32 + bb.min.x) as usize;
-                let y = (y as i32 + bb.min.y) as usize;
-                if v > 0.25 {
-                    let x = (x + x_pos).try_into().unwrap();
-                    let y = (y + y_pos).try_into().unwrap();
+                let x = x as i64 + bb.min.x as i64 + x_pos as i64;
+                let y = y as i64 + bb.min.y as i64 + y_pos as i64;
+                if let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) {
                     if x < buf.width() && y < buf.height() {
-                        buf.put_pixel(x, y, Rgb([255, 255, 255]));
+                        // blend the white text with the underlying pixel using
+                        // the glyph coverage as the alpha
+                        let pixel = buf.get_pixel_mut(x, y);
+                        for channel in pixel.0.iter_mut() {
+                            let blended = *channel as f32 * (1.0 - v) + 255.0 * v;
+                            *channel = blended.round() as u8;
+                        }
                     }
                 }
             })